@@ -0,0 +1,37 @@
+//! Workload generators shared by the benchmarks.
+//!
+//! Each generator is deterministic and returns the source together with the
+//! number of tokens it should scan to, so a benchmark can check it measured
+//! a working build.
+
+pub struct Workload {
+    pub name: &'static str,
+    pub source: String,
+    pub expected_tokens: usize,
+}
+
+/// Operators and delimiters only: exercises the single/double character paths.
+pub fn punctuation(lines: usize) -> Workload {
+    let line = "(){},.-+;*/ != == <= >= < > = !\n";
+    Workload {
+        name: "scan_punctuation",
+        source: line.repeat(lines),
+        expected_tokens: 19 * lines,
+    }
+}
+
+/// Declarations and control flow: exercises identifier and keyword matching.
+pub fn identifiers(lines: usize) -> Workload {
+    let mut source = String::new();
+    for i in 0..lines {
+        source.push_str(&format!(
+            "var value{} = other_{} and true or false; while (this) return nil;\n",
+            i, i
+        ));
+    }
+    Workload {
+        name: "scan_identifiers",
+        source,
+        expected_tokens: 16 * lines,
+    }
+}
@@ -0,0 +1,42 @@
+use std::time::{Duration, Instant};
+
+use jlox::Scanner;
+
+mod bench_support;
+
+use bench_support::Workload;
+
+const ITERATIONS: u32 = 10;
+
+fn bench(workload: &Workload) -> Duration {
+    let mut total = Duration::ZERO;
+    for _ in 0..ITERATIONS {
+        let started = Instant::now();
+        let mut scanner = Scanner::new(&workload.source);
+        scanner.scan_tokens();
+        total += started.elapsed();
+
+        assert!(scanner.errors().is_empty(), "{}: scan errors", workload.name);
+        assert_eq!(
+            scanner.tokens().len(),
+            workload.expected_tokens,
+            "{}: wrong token count",
+            workload.name
+        );
+    }
+    total / ITERATIONS
+}
+
+fn main() {
+    let workloads = [bench_support::punctuation(2_000), bench_support::identifiers(1_000)];
+
+    for workload in &workloads {
+        let mean = bench(workload);
+        println!(
+            "{:<20} {:>10.3?} per run ({} bytes)",
+            workload.name,
+            mean,
+            workload.source.len()
+        );
+    }
+}
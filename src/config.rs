@@ -0,0 +1,225 @@
+//! Settings read from a `jlox.toml` file.
+//!
+//! Only the subset of TOML the settings need is understood: `[section]`
+//! headers, `key = value` pairs whose values are strings, booleans or
+//! integers, and `#` comments. For example:
+//!
+//! ```toml
+//! [scanner]
+//! case_insensitive_keywords = true
+//!
+//! [diagnostics]
+//! max_errors = 10
+//! max_lexeme_preview = 80
+//!
+//! [repl]
+//! prompt = "lox> "
+//! banner = false
+//! ```
+//!
+//! Unknown sections and keys produce warnings rather than errors so that
+//! files written for newer versions still load.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::repl::ReplConfig;
+use crate::ScannerOptions;
+
+pub const CONFIG_FILE_NAME: &str = "jlox.toml";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    pub scanner: ScannerOptions,
+    pub repl: ReplConfig,
+}
+
+/// A setting that could not be read, pointing at the offending key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub path: Option<PathBuf>,
+    pub line: usize,
+    pub key: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "{}:", path.display())?;
+        }
+        if self.key.is_empty() {
+            write!(f, "{}: {}", self.line, self.message)
+        } else {
+            write!(f, "{}: '{}' {}", self.line, self.key, self.message)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    String(String),
+    Bool(bool),
+    Integer(i64),
+}
+
+impl Value {
+    fn kind(&self) -> &'static str {
+        match self {
+            Value::String(_) => "a string",
+            Value::Bool(_) => "a boolean",
+            Value::Integer(_) => "an integer",
+        }
+    }
+}
+
+impl Config {
+    /// Parses settings from `text`, returning them along with warnings for
+    /// anything that was ignored.
+    pub fn parse(text: &str) -> Result<(Config, Vec<String>), ConfigError> {
+        let mut config = Config::default();
+        let mut warnings = Vec::new();
+        let mut section = String::new();
+
+        for (index, raw) in text.lines().enumerate() {
+            let line = index + 1;
+            let error = |key: &str, message: &str| ConfigError {
+                path: None,
+                line,
+                key: key.to_string(),
+                message: message.to_string(),
+            };
+
+            let content = strip_comment(raw).trim();
+            if content.is_empty() {
+                continue;
+            }
+
+            if let Some(header) = content.strip_prefix('[') {
+                match header.strip_suffix(']') {
+                    Some(name) => section = name.trim().to_string(),
+                    None => return Err(error("", "section header is missing ']'")),
+                }
+                continue;
+            }
+
+            let (key, value) = match content.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => return Err(error("", "expected 'key = value'")),
+            };
+            let full_key = if section.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}.{}", section, key)
+            };
+            let value = parse_value(value).map_err(|message| error(&full_key, &message))?;
+
+            let type_error = |wanted: &str, found: &Value| {
+                error(&full_key, &format!("must be {}, found {}", wanted, found.kind()))
+            };
+            match (full_key.as_str(), value) {
+                ("diagnostics.max_errors", Value::Integer(n)) if n >= 0 => {
+                    config.scanner.diagnostics.max_errors = n as usize
+                }
+                ("scanner.case_insensitive_keywords", Value::Bool(b)) => {
+                    config.scanner.case_insensitive_keywords = b
+                }
+                ("diagnostics.max_lexeme_preview", Value::Integer(n)) if n >= 0 => {
+                    config.scanner.diagnostics.max_lexeme_preview = n as usize
+                }
+                ("repl.prompt", Value::String(s)) => config.repl.prompt = s,
+                ("repl.banner", Value::Bool(b)) => config.repl.banner = b,
+                ("diagnostics.max_errors", other) => {
+                    return Err(type_error("a non-negative integer", &other))
+                }
+                ("scanner.case_insensitive_keywords", other) => {
+                    return Err(type_error("a boolean", &other))
+                }
+                ("diagnostics.max_lexeme_preview", other) => {
+                    return Err(type_error("a non-negative integer", &other))
+                }
+                ("repl.prompt", other) => return Err(type_error("a string", &other)),
+                ("repl.banner", other) => return Err(type_error("a boolean", &other)),
+                _ => warnings.push(format!("line {}: unknown setting '{}'", line, full_key)),
+            }
+        }
+
+        Ok((config, warnings))
+    }
+
+    /// Reads and parses the settings file at `path`.
+    pub fn load(path: &Path) -> Result<(Config, Vec<String>), ConfigError> {
+        let text = fs::read_to_string(path).map_err(|e| ConfigError {
+            path: Some(path.to_path_buf()),
+            line: 0,
+            key: String::new(),
+            message: format!("could not be read: {}", e),
+        })?;
+
+        Config::parse(&text).map_err(|mut e| {
+            e.path = Some(path.to_path_buf());
+            e
+        })
+    }
+}
+
+/// Finds the nearest `jlox.toml` in `dir` or any of its ancestors.
+pub fn discover(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => (),
+        }
+    }
+
+    line
+}
+
+fn parse_value(text: &str) -> Result<Value, String> {
+    match text {
+        "true" => return Ok(Value::Bool(true)),
+        "false" => return Ok(Value::Bool(false)),
+        _ => (),
+    }
+
+    if let Some(body) = text.strip_prefix('"') {
+        let body = match body.strip_suffix('"') {
+            Some(body) => body,
+            None => return Err("has an unterminated string".to_string()),
+        };
+
+        let mut value = String::new();
+        let mut chars = body.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                value.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('"') => value.push('"'),
+                Some('\\') => value.push('\\'),
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                _ => return Err("has an invalid escape sequence".to_string()),
+            }
+        }
+        return Ok(Value::String(value));
+    }
+
+    text.parse::<i64>()
+        .map(Value::Integer)
+        .map_err(|_| format!("has an unrecognized value '{}'", text))
+}
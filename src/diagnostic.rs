@@ -0,0 +1,277 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, Write};
+
+/// Limits on how much source text diagnostics and token dumps echo back,
+/// so a single enormous line (minified or generated code) stays readable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagnosticConfig {
+    /// Errors reported per run before the rest are only counted; 0 means no
+    /// limit.
+    pub max_errors: usize,
+    /// Characters of a lexeme to show before eliding the rest; 0 means no
+    /// limit.
+    pub max_lexeme_preview: usize,
+}
+
+impl Default for DiagnosticConfig {
+    fn default() -> Self {
+        DiagnosticConfig {
+            max_errors: 20,
+            max_lexeme_preview: 40,
+        }
+    }
+}
+
+impl DiagnosticConfig {
+    /// `text` cut to `max_lexeme_preview` characters, ending in `…` if
+    /// anything was dropped.
+    pub fn preview<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self.truncate(text) {
+            Some(prefix) => Cow::Owned(format!("{}…", prefix)),
+            None => Cow::Borrowed(text),
+        }
+    }
+
+    /// The longest prefix of `text` within the limit, or `None` if all of
+    /// it fits.
+    pub fn truncate<'a>(&self, text: &'a str) -> Option<&'a str> {
+        if self.max_lexeme_preview == 0 {
+            return None;
+        }
+        text.char_indices()
+            .nth(self.max_lexeme_preview)
+            .map(|(end, _)| &text[..end])
+    }
+}
+
+/// What went wrong in a `ScanError`, for tools that act on diagnostics
+/// rather than display them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScanErrorKind {
+    /// Not about the source text, such as a failure to read it.
+    Other,
+    UnexpectedCharacter,
+    InvalidNumber,
+    UnterminatedString,
+    /// Identifier characters glued onto a number, as in `123abc`.
+    InvalidNumericLiteral,
+    /// A number too large for, or too small to be distinguished from zero
+    /// in, the representation numbers use.
+    NumberOutOfRange,
+    /// A number literal longer than `crate::MAX_NUMBER_LENGTH`.
+    NumberTooLong,
+}
+
+impl ScanErrorKind {
+    /// The stable code shown to users, such as `E003`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ScanErrorKind::Other => "E000",
+            ScanErrorKind::UnexpectedCharacter => "E001",
+            ScanErrorKind::InvalidNumber => "E002",
+            ScanErrorKind::UnterminatedString => "E003",
+            ScanErrorKind::InvalidNumericLiteral => "E004",
+            ScanErrorKind::NumberOutOfRange => "E005",
+            ScanErrorKind::NumberTooLong => "E006",
+        }
+    }
+}
+
+/// Where an edit would fix an error: inserting at `column` when it equals
+/// `end_column`, otherwise replacing the characters between them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixSpan {
+    pub line: usize,
+    pub column: usize,
+    pub end_column: usize,
+}
+
+/// An error found while turning source text into tokens.
+///
+/// Lines and columns start at 1; `end_column` is exclusive. A column of 0
+/// means the error has no position within the line. `file` is filled in by
+/// `Lox` when the source being run has a name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanError {
+    pub file: Option<String>,
+    pub line: usize,
+    pub column: usize,
+    pub end_column: usize,
+    pub kind: ScanErrorKind,
+    pub message: String,
+    /// Set when there is an obvious fix, like closing an unterminated string.
+    pub fix_span: Option<FixSpan>,
+}
+
+impl ScanError {
+    /// An error tied to a line but not to any particular text on it.
+    pub fn new(line: usize, message: impl Into<String>) -> Self {
+        ScanError {
+            file: None,
+            line,
+            column: 0,
+            end_column: 0,
+            kind: ScanErrorKind::Other,
+            message: message.into(),
+            fix_span: None,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "[{} line {}] Error: {}", file, self.line, self.message),
+            None => write!(f, "[line {}] Error: {}", self.line, self.message),
+        }
+    }
+}
+
+/// A bug in jlox itself, caught before it could abort the process.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InternalError {
+    pub file: Option<String>,
+    /// The last source line the pipeline reached before failing, if any.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for InternalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "[{} line {}] ", file, line)?,
+            (None, Some(line)) => write!(f, "[line {}] ", line)?,
+            (Some(file), None) => write!(f, "[{}] ", file)?,
+            (None, None) => (),
+        }
+        write!(
+            f,
+            "Internal error: {}\nThis is a bug in jlox; please report it along with the script that triggered it.",
+            self.message
+        )
+    }
+}
+
+/// Receives every diagnostic the interpreter produces.
+///
+/// `Lox` owns one handler and routes all errors through it, so embedders can
+/// surface them in their own UI instead of on stderr. Only scan errors must
+/// be handled; the other methods do nothing unless overridden.
+pub trait DiagnosticHandler {
+    fn on_scan_error(&mut self, e: &ScanError);
+
+    fn on_internal_error(&mut self, _e: &InternalError) {}
+
+    /// Called at the end of a run that found more errors than
+    /// `DiagnosticConfig::max_errors` allowed to be reported.
+    fn on_error_limit(&mut self, _total: usize, _shown: usize) {}
+}
+
+/// The line reported when errors were held back, such as
+/// `error: aborting due to 57 previous errors (37 not shown)`.
+pub fn error_limit_summary(total: usize, shown: usize) -> String {
+    format!(
+        "error: aborting due to {} previous errors ({} not shown)",
+        total,
+        total - shown
+    )
+}
+
+/// The default handler: prints each diagnostic to stderr.
+#[derive(Debug, Default)]
+pub struct StderrHandler;
+
+impl DiagnosticHandler for StderrHandler {
+    fn on_scan_error(&mut self, e: &ScanError) {
+        eprintln!("{}", e);
+    }
+
+    fn on_internal_error(&mut self, e: &InternalError) {
+        eprintln!("{}", e);
+    }
+
+    fn on_error_limit(&mut self, total: usize, shown: usize) {
+        eprintln!("{}", error_limit_summary(total, shown));
+    }
+}
+
+/// Prints each diagnostic as a single-line JSON object on stdout, shaped
+/// after LSP diagnostics, for editor integrations.
+///
+/// When errors were held back, the diagnostics of a run are followed by a
+/// record saying how many, such as
+/// `{"errorLimit":{"total":57,"shown":20},"message":"..."}`.
+#[derive(Debug, Default)]
+pub struct JsonHandler;
+
+impl JsonHandler {
+    fn write(line: fmt::Arguments) {
+        // A reader that stopped early, like `head`, is not an error.
+        match writeln!(io::stdout(), "{}", line) {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+                panic!("failed to write diagnostics: {}", e)
+            }
+            _ => (),
+        }
+    }
+}
+
+impl DiagnosticHandler for JsonHandler {
+    fn on_scan_error(&mut self, e: &ScanError) {
+        Self::write(format_args!(
+            "{{\"file\":{},\"line\":{},\"column\":{},\"endColumn\":{},\"severity\":\"error\",\"code\":{},\"message\":{},\"fixSpan\":{}}}",
+            json_string(e.file.as_deref().unwrap_or("")),
+            e.line,
+            e.column,
+            e.end_column,
+            json_string(e.code()),
+            json_string(&e.message),
+            match e.fix_span {
+                Some(fix) => format!(
+                    "{{\"line\":{},\"column\":{},\"endColumn\":{}}}",
+                    fix.line, fix.column, fix.end_column
+                ),
+                None => "null".to_string(),
+            }
+        ));
+    }
+
+    fn on_error_limit(&mut self, total: usize, shown: usize) {
+        Self::write(format_args!(
+            "{{\"errorLimit\":{{\"total\":{},\"shown\":{}}},\"message\":{}}}",
+            total,
+            shown,
+            json_string(&error_limit_summary(total, shown))
+        ));
+    }
+
+    // A bug in jlox is not a diagnostic about the script, so it goes to
+    // stderr for a person rather than to the editor.
+    fn on_internal_error(&mut self, e: &InternalError) {
+        eprintln!("{}", e);
+    }
+}
+
+/// Quotes and escapes `s` as a JSON string literal.
+pub fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
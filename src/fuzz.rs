@@ -0,0 +1,47 @@
+//! Fuzzing entry points, compiled only with the `fuzzing` feature.
+//!
+//! A `cargo fuzz` target only needs to forward its input:
+//!
+//! ```ignore
+//! fuzz_target!(|data: &[u8]| jlox::fuzz::scan(data));
+//! ```
+
+use crate::source::SourceFile;
+use crate::Scanner;
+
+/// Scans arbitrary bytes and panics if any scanner invariant is violated.
+pub fn scan(data: &[u8]) {
+    let source = String::from_utf8_lossy(data);
+    let mut scanner = Scanner::new(&source);
+    scanner.scan_tokens();
+
+    let file = SourceFile::new("fuzz", source.as_ref());
+    let last_line = file.line_count();
+    for error in scanner.errors() {
+        assert!(
+            (1..=last_line).contains(&error.line),
+            "error on line {} of a {}-line source",
+            error.line,
+            last_line
+        );
+        if error.column != 0 {
+            assert!(
+                file.offset(error.line, error.column).is_some(),
+                "error at column {} is past the end of line {}",
+                error.column,
+                error.line
+            );
+        }
+    }
+
+    for token in scanner.tokens() {
+        // Slicing the source by the span checks it is in bounds and that both
+        // ends are on char boundaries.
+        let span = token.span();
+        assert!(span.end <= source.len(), "token {:?} ends past the source", token);
+        assert_eq!(source.get(span.range()), Some(token.lexeme()));
+        // A token's line is the one it ends on.
+        let last_char = token.lexeme().char_indices().last().map_or(0, |(i, _)| i);
+        assert_eq!(file.line_col(span.start + last_char).0, token.line(), "{:?}", token);
+    }
+}
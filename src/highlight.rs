@@ -0,0 +1,127 @@
+use crate::lex::{lex_full, FullTokenKind};
+use crate::TokenType;
+
+/// The syntactic category of a run of source text, for colorizing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenClass {
+    Keyword,
+    Identifier,
+    Number,
+    String,
+    Comment,
+    Operator,
+    Punctuation,
+    Whitespace,
+    Error,
+}
+
+impl TokenClass {
+    pub fn css_class(&self) -> &'static str {
+        match self {
+            TokenClass::Keyword => "keyword",
+            TokenClass::Identifier => "identifier",
+            TokenClass::Number => "number",
+            TokenClass::String => "string",
+            TokenClass::Comment => "comment",
+            TokenClass::Operator => "operator",
+            TokenClass::Punctuation => "punctuation",
+            TokenClass::Whitespace => "whitespace",
+            TokenClass::Error => "error",
+        }
+    }
+
+    fn of(token_type: &TokenType) -> Self {
+        match token_type {
+            TokenType::LeftParen
+            | TokenType::RightParen
+            | TokenType::LeftBrace
+            | TokenType::RightBrace
+            | TokenType::Comma
+            | TokenType::Dot
+            | TokenType::QuestionDot
+            | TokenType::Semicolon
+            | TokenType::Eof => TokenClass::Punctuation,
+            TokenType::Minus
+            | TokenType::Plus
+            | TokenType::Slash
+            | TokenType::Star
+            | TokenType::Bang
+            | TokenType::BangEqual
+            | TokenType::Equal
+            | TokenType::EqualEqual
+            | TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::Less
+            | TokenType::LessEqual => TokenClass::Operator,
+            TokenType::Identifier => TokenClass::Identifier,
+            TokenType::String(_) => TokenClass::String,
+            TokenType::Number(_) => TokenClass::Number,
+            _ => TokenClass::Keyword,
+        }
+    }
+}
+
+/// A classified byte range of the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub class: TokenClass,
+}
+
+/// Splits `source` into classified spans.
+///
+/// The spans are in order and cover every byte exactly once; their offsets
+/// always fall on char boundaries. Text the scanner rejects is kept as an
+/// `Error` span rather than dropped, and adjacent whitespace is merged.
+pub fn highlight(source: &str) -> Vec<Span> {
+    lex_full(source)
+        .into_iter()
+        .map(|entry| Span {
+            start: entry.span.start,
+            end: entry.span.end,
+            class: match entry.kind {
+                FullTokenKind::Token(token_type) => TokenClass::of(&token_type),
+                FullTokenKind::Whitespace => TokenClass::Whitespace,
+                FullTokenKind::Comment => TokenClass::Comment,
+                FullTokenKind::Error(_) => TokenClass::Error,
+            },
+        })
+        .collect()
+}
+
+/// Renders `source` as HTML with each non-whitespace span wrapped in a
+/// `<span class="...">` named after its class.
+pub fn highlight_html(source: &str) -> String {
+    let mut html = String::from("<pre class=\"lox\">");
+
+    for span in highlight(source) {
+        let text = escape_html(&source[span.start..span.end]);
+        if span.class == TokenClass::Whitespace {
+            html.push_str(&text);
+        } else {
+            html.push_str(&format!(
+                "<span class=\"{}\">{}</span>",
+                span.class.css_class(),
+                text
+            ));
+        }
+    }
+
+    html.push_str("</pre>\n");
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
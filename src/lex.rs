@@ -0,0 +1,67 @@
+//! Lossless tokenization, for tools that rewrite source rather than run it.
+
+use crate::diagnostic::ScanErrorKind;
+use crate::{Scanner, Span, TokenType};
+
+/// What a `FullToken` covers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FullTokenKind<'a> {
+    Token(TokenType<'a>),
+    Whitespace,
+    Comment,
+    /// Text the scanner rejected.
+    Error(ScanErrorKind),
+}
+
+/// A run of source text with what it is and where it starts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FullToken<'a> {
+    pub kind: FullTokenKind<'a>,
+    pub text: &'a str,
+    /// The line the text starts on.
+    pub line: usize,
+    pub span: Span,
+}
+
+/// Splits `source` into tokens, whitespace, comments and errors.
+///
+/// The entries are in order and cover every byte exactly once, so
+/// concatenating their `text` reproduces `source`. Adjacent whitespace,
+/// line breaks included, is merged into one entry.
+pub fn lex_full(source: &str) -> Vec<FullToken<'_>> {
+    let mut scanner = Scanner::new(source);
+    let mut entries: Vec<FullToken> = Vec::new();
+
+    while !scanner.is_at_end() {
+        scanner.start = scanner.current;
+        let line = scanner.line;
+        let (tokens, errors) = (scanner.tokens.len(), scanner.errors.len());
+        scanner.scan_token();
+
+        let text = &source[scanner.start..scanner.current];
+        let kind = if let Some(error) = scanner.errors.get(errors) {
+            FullTokenKind::Error(error.kind)
+        } else if let Some(token) = scanner.tokens.get(tokens) {
+            FullTokenKind::Token(token.token_type)
+        } else if text.starts_with("//") {
+            FullTokenKind::Comment
+        } else {
+            FullTokenKind::Whitespace
+        };
+
+        match entries.last_mut() {
+            Some(last) if kind == FullTokenKind::Whitespace && last.kind == kind => {
+                last.span.end = scanner.current;
+                last.text = &source[last.span.range()];
+            }
+            _ => entries.push(FullToken {
+                kind,
+                text,
+                line,
+                span: Span::new(scanner.start, scanner.current),
+            }),
+        }
+    }
+
+    entries
+}
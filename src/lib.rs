@@ -0,0 +1,929 @@
+use std::cell::Cell;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+use std::time::{Duration, Instant};
+
+pub mod config;
+pub mod diagnostic;
+#[cfg(feature = "fuzzing")]
+pub mod fuzz;
+pub mod highlight;
+pub mod lex;
+pub mod repl;
+pub mod source;
+pub mod stream;
+pub mod watch;
+pub mod workspace;
+
+use diagnostic::{
+    DiagnosticConfig, DiagnosticHandler, FixSpan, InternalError, ScanError, ScanErrorKind,
+    StderrHandler,
+};
+use source::SourceFile;
+use stream::StreamingScanner;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Optional capabilities compiled into this build. Anything that reports or
+/// depends on which features are available should consult this rather than
+/// checking cargo features directly, so the answers cannot drift.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Features {
+    /// Fuzzing entry points (`jlox::fuzz`).
+    pub fuzzing: bool,
+}
+
+impl Features {
+    pub const fn enabled() -> Self {
+        Features {
+            fuzzing: cfg!(feature = "fuzzing"),
+        }
+    }
+
+    /// Names of the enabled features, in a stable order.
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        if self.fuzzing {
+            names.push("fuzzing");
+        }
+        names
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.names().contains(&name)
+    }
+}
+
+thread_local! {
+    // The source line the pipeline most recently reached, so an internal
+    // error can say roughly where it happened.
+    static BREADCRUMB: Cell<Option<usize>> = const { Cell::new(None) };
+    // Whether this thread is inside `Lox::guarded`.
+    static GUARDED: Cell<bool> = const { Cell::new(false) };
+}
+
+fn set_breadcrumb(line: Option<usize>) {
+    BREADCRUMB.with(|b| b.set(line));
+}
+
+/// Wraps the panic hook in place on first use so that it stays quiet inside
+/// `Lox::guarded`, where panics are reported as internal errors instead,
+/// and behaves as before everywhere else. The check is per thread, so
+/// concurrent interpreters cannot leave the hook swapped out.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !GUARDED.with(Cell::get) {
+                previous(info);
+            }
+        }));
+    });
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenType<'a> {
+    // Single-character tokens.
+    LeftParen,
+    RightParen,
+    LeftBrace,
+    RightBrace,
+    Comma,
+    Dot,
+    Minus,
+    Plus,
+    Semicolon,
+    Slash,
+    Star,
+    // One or two character tokens.
+    Bang,
+    BangEqual,
+    Equal,
+    EqualEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+    QuestionDot,
+    // Literals.
+    Identifier,
+    String(&'a str),
+    Number(f32),
+    // Keywords.
+    And,
+    Class,
+    Else,
+    False,
+    Func,
+    For,
+    If,
+    Nil,
+    Or,
+    Print,
+    Return,
+    Super,
+    This,
+    True,
+    Var,
+    While,
+    Eof,
+}
+
+impl<'a> TokenType<'a> {
+    /// Rebinds the borrowed payload of a string literal, leaving every other
+    /// variant unchanged.
+    pub fn map_str<'b>(self, f: impl FnOnce(&'a str) -> &'b str) -> TokenType<'b> {
+        match self {
+            TokenType::LeftParen => TokenType::LeftParen,
+            TokenType::RightParen => TokenType::RightParen,
+            TokenType::LeftBrace => TokenType::LeftBrace,
+            TokenType::RightBrace => TokenType::RightBrace,
+            TokenType::Comma => TokenType::Comma,
+            TokenType::Dot => TokenType::Dot,
+            TokenType::Minus => TokenType::Minus,
+            TokenType::Plus => TokenType::Plus,
+            TokenType::Semicolon => TokenType::Semicolon,
+            TokenType::Slash => TokenType::Slash,
+            TokenType::Star => TokenType::Star,
+            TokenType::Bang => TokenType::Bang,
+            TokenType::BangEqual => TokenType::BangEqual,
+            TokenType::Equal => TokenType::Equal,
+            TokenType::EqualEqual => TokenType::EqualEqual,
+            TokenType::Greater => TokenType::Greater,
+            TokenType::GreaterEqual => TokenType::GreaterEqual,
+            TokenType::Less => TokenType::Less,
+            TokenType::LessEqual => TokenType::LessEqual,
+            TokenType::QuestionDot => TokenType::QuestionDot,
+            TokenType::Identifier => TokenType::Identifier,
+            TokenType::String(s) => TokenType::String(f(s)),
+            TokenType::Number(n) => TokenType::Number(n),
+            TokenType::And => TokenType::And,
+            TokenType::Class => TokenType::Class,
+            TokenType::Else => TokenType::Else,
+            TokenType::False => TokenType::False,
+            TokenType::Func => TokenType::Func,
+            TokenType::For => TokenType::For,
+            TokenType::If => TokenType::If,
+            TokenType::Nil => TokenType::Nil,
+            TokenType::Or => TokenType::Or,
+            TokenType::Print => TokenType::Print,
+            TokenType::Return => TokenType::Return,
+            TokenType::Super => TokenType::Super,
+            TokenType::This => TokenType::This,
+            TokenType::True => TokenType::True,
+            TokenType::Var => TokenType::Var,
+            TokenType::While => TokenType::While,
+            TokenType::Eof => TokenType::Eof,
+        }
+    }
+
+    /// The variant's name, without any literal payload.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenType::LeftParen => "LeftParen",
+            TokenType::RightParen => "RightParen",
+            TokenType::LeftBrace => "LeftBrace",
+            TokenType::RightBrace => "RightBrace",
+            TokenType::Comma => "Comma",
+            TokenType::Dot => "Dot",
+            TokenType::Minus => "Minus",
+            TokenType::Plus => "Plus",
+            TokenType::Semicolon => "Semicolon",
+            TokenType::Slash => "Slash",
+            TokenType::Star => "Star",
+            TokenType::Bang => "Bang",
+            TokenType::BangEqual => "BangEqual",
+            TokenType::Equal => "Equal",
+            TokenType::EqualEqual => "EqualEqual",
+            TokenType::Greater => "Greater",
+            TokenType::GreaterEqual => "GreaterEqual",
+            TokenType::Less => "Less",
+            TokenType::LessEqual => "LessEqual",
+            TokenType::QuestionDot => "QuestionDot",
+            TokenType::Identifier => "Identifier",
+            TokenType::String(_) => "String",
+            TokenType::Number(_) => "Number",
+            TokenType::And => "And",
+            TokenType::Class => "Class",
+            TokenType::Else => "Else",
+            TokenType::False => "False",
+            TokenType::Func => "Func",
+            TokenType::For => "For",
+            TokenType::If => "If",
+            TokenType::Nil => "Nil",
+            TokenType::Or => "Or",
+            TokenType::Print => "Print",
+            TokenType::Return => "Return",
+            TokenType::Super => "Super",
+            TokenType::This => "This",
+            TokenType::True => "True",
+            TokenType::Var => "Var",
+            TokenType::While => "While",
+            TokenType::Eof => "Eof",
+        }
+    }
+}
+
+/// How long each stage of the pipeline took, accumulated across runs until
+/// taken with `Lox::take_timings`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Timings {
+    pub scan: Duration,
+    pub tokens: usize,
+}
+
+pub struct Lox {
+    pub had_error: bool,
+    pub had_internal_error: bool,
+    /// The error that stopped `run_reader` reading its source.
+    pub read_error: Option<io::Error>,
+    /// The first error writing program output. Nothing more is written once
+    /// it is set.
+    pub output_error: Option<io::Error>,
+    timings: Timings,
+    // Errors found in the current run, whether or not they were reported.
+    errors_seen: usize,
+    handler: Box<dyn DiagnosticHandler>,
+    output: Box<dyn Write>,
+    // Set once the reader of `output` has gone away, as when piped to `head`.
+    output_closed: bool,
+    source_name: Option<String>,
+    scanner_options: ScannerOptions,
+}
+
+impl Default for Lox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lox {
+    pub fn new() -> Self {
+        Self::with_handler(Box::new(StderrHandler))
+    }
+
+    pub fn with_handler(handler: Box<dyn DiagnosticHandler>) -> Self {
+        Lox {
+            had_error: false,
+            had_internal_error: false,
+            read_error: None,
+            output_error: None,
+            timings: Timings::default(),
+            errors_seen: 0,
+            handler,
+            output: Box::new(io::stdout()),
+            output_closed: false,
+            source_name: None,
+            scanner_options: ScannerOptions::default(),
+        }
+    }
+
+    /// Sends program output, which is stdout by default, to `output`.
+    pub fn set_output(&mut self, output: Box<dyn Write>) {
+        self.output = output;
+    }
+
+    pub fn set_scanner_options(&mut self, options: ScannerOptions) {
+        self.scanner_options = options;
+    }
+
+    /// Names the source passed to subsequent runs, typically its file path,
+    /// so diagnostics can say which file they came from.
+    pub fn set_source_name(&mut self, name: Option<String>) {
+        self.source_name = name;
+    }
+
+    pub fn run(&mut self, source: &str) {
+        self.guarded(|lox| {
+            for token in lox.scan(source) {
+                lox.dump(&token);
+            }
+        });
+    }
+
+    /// Reports every diagnostic in `source` without running it.
+    pub fn check(&mut self, source: &str) {
+        self.guarded(|lox| {
+            lox.scan(source);
+        });
+    }
+
+    /// Returns the timings recorded since the last call, resetting them.
+    pub fn take_timings(&mut self) -> Timings {
+        std::mem::take(&mut self.timings)
+    }
+
+    /// Runs `f`, turning a panic inside it into an internal-error diagnostic
+    /// so a bug in jlox does not take down the REPL or an embedding program.
+    ///
+    /// Each call is one run for the purposes of `DiagnosticConfig::max_errors`.
+    fn guarded(&mut self, f: impl FnOnce(&mut Self)) {
+        set_breadcrumb(None);
+        self.errors_seen = 0;
+        install_panic_hook();
+        let was_guarded = GUARDED.with(|g| g.replace(true));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| f(self)));
+        GUARDED.with(|g| g.set(was_guarded));
+
+        if let Err(payload) = result {
+            let message = match payload.downcast_ref::<&str>() {
+                Some(s) => s.to_string(),
+                None => match payload.downcast_ref::<String>() {
+                    Some(s) => s.clone(),
+                    None => "unknown panic".to_string(),
+                },
+            };
+            let error = InternalError {
+                file: self.source_name.clone(),
+                line: BREADCRUMB.with(Cell::get),
+                message,
+            };
+            self.handler.on_internal_error(&error);
+            self.had_internal_error = true;
+        }
+
+        let max = self.scanner_options.diagnostics.max_errors;
+        if max != 0 && self.errors_seen > max {
+            self.handler.on_error_limit(self.errors_seen, max);
+        }
+    }
+
+    fn scan<'a>(&mut self, source: &'a str) -> Vec<Token<'a>> {
+        let started = Instant::now();
+        let mut scanner = Scanner::with_options(source, self.scanner_options.clone());
+        scanner.scan_tokens();
+        self.timings.scan += started.elapsed();
+        self.timings.tokens += scanner.tokens.len();
+
+        for error in scanner.errors.drain(..) {
+            self.report(error);
+        }
+
+        scanner.tokens
+    }
+
+    /// Prints `token` for the token dump, eliding long lexemes.
+    fn dump(&mut self, token: &Token) {
+        if self.output_closed {
+            return;
+        }
+
+        let diagnostics = &self.scanner_options.diagnostics;
+        let result = match diagnostics.truncate(token.lexeme) {
+            None => writeln!(self.output, "{:?}", token),
+            Some(lexeme) => {
+                let elided = token.lexeme[lexeme.len()..].chars().count();
+                let token_type = token
+                    .token_type
+                    .map_str(|s| diagnostics.truncate(s).unwrap_or(s));
+                let preview = Token { token_type, lexeme, ..*token };
+                writeln!(self.output, "{:?} (… {} more characters)", preview, elided)
+            }
+        };
+        match result {
+            Ok(()) => (),
+            Err(e) => {
+                self.output_closed = true;
+                if e.kind() != io::ErrorKind::BrokenPipe {
+                    self.output_error = Some(e);
+                }
+            }
+        }
+    }
+
+    fn report(&mut self, mut error: ScanError) {
+        self.had_error = true;
+        self.errors_seen += 1;
+
+        let max = self.scanner_options.diagnostics.max_errors;
+        if max == 0 || self.errors_seen <= max {
+            error.file = self.source_name.clone();
+            self.handler.on_scan_error(&error);
+        }
+    }
+
+    /// Like `run`, but tokenizes incrementally so the whole source never has
+    /// to be held in memory at once. If `reader` fails, what was read before
+    /// is still run and the error is kept in `read_error`.
+    pub fn run_reader(&mut self, reader: impl Read) {
+        self.guarded(|lox| {
+            let options = lox.scanner_options.clone();
+            let mut scanner = StreamingScanner::with_options(reader, options);
+            loop {
+                // Only time the scanner itself, not printing its output.
+                let started = Instant::now();
+                let item = scanner.next();
+                lox.timings.scan += started.elapsed();
+
+                match item {
+                    None => break,
+                    Some(Ok(token)) => {
+                        lox.timings.tokens += 1;
+                        lox.dump(&token.as_token());
+                    }
+                    Some(Err(error)) => lox.report(error),
+                }
+            }
+            lox.read_error = scanner.take_read_error();
+        });
+    }
+}
+
+/// A range of byte offsets into the source, end exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// The smallest span covering both `self` and `other`.
+    pub fn to(&self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Token<'a> {
+    token_type: TokenType<'a>,
+    lexeme: &'a str,
+    line: usize,
+    span: Span,
+}
+
+impl<'a> Token<'a> {
+    pub fn new(token_type: TokenType<'a>, lexeme: &'a str, line: usize, span: Span) -> Self {
+        Token {
+            token_type,
+            lexeme,
+            line,
+            span,
+        }
+    }
+
+    pub fn token_type(&self) -> TokenType<'a> {
+        self.token_type
+    }
+
+    pub fn lexeme(&self) -> &'a str {
+        self.lexeme
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} {} {}", self.token_type, self.lexeme, self.line)
+    }
+}
+
+/// Settings that change how source text is tokenized.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScannerOptions {
+    /// Recognize keywords regardless of case, so `Print` and `IF` work.
+    /// Meant for teaching, where capitalization slips are common.
+    pub case_insensitive_keywords: bool,
+    /// Treat input that ends partway through a token as incomplete rather
+    /// than wrong: a string still open at the end of the source becomes a
+    /// `String` token instead of an error, and `Scanner::is_incomplete`
+    /// reports it. For callers like the REPL that scan text as it is typed.
+    pub tolerate_incomplete: bool,
+    /// How many errors are reported and how much source text they and
+    /// token dumps may echo.
+    pub diagnostics: DiagnosticConfig,
+}
+
+/// Longest number literal accepted, in characters. Far more than any real
+/// number needs, but bounds the work spent parsing pathological input.
+pub const MAX_NUMBER_LENGTH: usize = 512;
+
+pub struct Scanner<'a> {
+    source: &'a str,
+    options: ScannerOptions,
+    tokens: Vec<Token<'a>>,
+    errors: Vec<ScanError>,
+    start: usize,
+    current: usize,
+    line: usize,
+    // The line `source` starts on, which is not 1 when it is a later part
+    // of a longer input.
+    first_line: usize,
+    // Line starts for turning offsets into error positions, indexed the
+    // first time an error needs them.
+    lines: Option<SourceFile<'a>>,
+    incomplete: bool,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Self::with_options(source, ScannerOptions::default())
+    }
+
+    pub fn with_options(source: &'a str, options: ScannerOptions) -> Self {
+        Scanner {
+            source,
+            options,
+            // Real code averages a token every few bytes; reserving up front
+            // avoids repeated regrowth while scanning large files.
+            tokens: Vec::with_capacity(source.len() / 4),
+            errors: Vec::new(),
+            start: 0,
+            current: 0,
+            line: 1,
+            first_line: 1,
+            lines: None,
+            incomplete: false,
+        }
+    }
+
+    pub fn add_token(&mut self, token_type: TokenType<'a>) {
+        let token = Token::new(
+            token_type,
+            &self.source[self.start..self.current],
+            self.line,
+            Span::new(self.start, self.current),
+        );
+
+        self.tokens.push(token);
+    }
+
+    pub fn add_literal(&mut self, token_type: TokenType<'a>) {
+        let token = Token::new(
+            token_type,
+            &self.source[self.start..self.current],
+            self.line,
+            Span::new(self.start, self.current),
+        );
+
+        self.tokens.push(token);
+    }
+
+    pub fn scan_token(&mut self) {
+        set_breadcrumb(Some(self.line));
+        match self.advance() {
+            Some('(') => self.add_token(TokenType::LeftParen),
+            Some(')') => self.add_token(TokenType::RightParen),
+            Some('{') => self.add_token(TokenType::LeftBrace),
+            Some('}') => self.add_token(TokenType::RightBrace),
+            Some(',') => self.add_token(TokenType::Comma),
+            Some('.') => self.add_token(TokenType::Dot),
+            Some('-') => self.add_token(TokenType::Minus),
+            Some('+') => self.add_token(TokenType::Plus),
+            Some(';') => self.add_token(TokenType::Semicolon),
+            Some('*') => self.add_token(TokenType::Star),
+            Some('\n') => self.newline(),
+            Some(' ') | Some('\r') | Some('\t') => (),
+            Some('/') => {
+                if self.check('/') {
+                    while (self.peek() != Some('\n')) && !self.is_at_end() {
+                        self.advance();
+                    }
+                } else {
+                    self.add_token(TokenType::Slash);
+                }
+            }
+            Some('!') => {
+                if self.check('=') {
+                    self.add_token(TokenType::BangEqual)
+                } else {
+                    self.add_token(TokenType::Bang)
+                }
+            }
+            Some('=') => {
+                if self.check('=') {
+                    self.add_token(TokenType::EqualEqual)
+                } else {
+                    self.add_token(TokenType::Equal)
+                }
+            }
+            Some('<') => {
+                if self.check('=') {
+                    self.add_token(TokenType::LessEqual)
+                } else {
+                    self.add_token(TokenType::Less)
+                }
+            }
+            Some('>') => {
+                if self.check('=') {
+                    self.add_token(TokenType::GreaterEqual)
+                } else {
+                    self.add_token(TokenType::Greater)
+                }
+            }
+            // Only `?.` for now; a lone `?` is still unexpected.
+            Some('?') if self.check('.') => self.add_token(TokenType::QuestionDot),
+            Some('"') => {
+				// Rather than swallow the rest of the file, an unterminated
+				// string gives up at the first line break followed by a line
+				// that reads like code and cannot close it, so later lines
+				// still scan normally. Input that more lines may yet finish,
+				// as in the REPL, is never cut short.
+				let recover = !self.options.tolerate_incomplete;
+				while (self.peek() != Some('"')) && !self.is_at_end() {
+					if recover && self.peek() == Some('\n') && self.next_line_looks_like_code() {
+						break;
+					}
+					if self.advance() == Some('\n') {
+						self.newline();
+					}
+				}
+
+				if self.is_at_end() && self.options.tolerate_incomplete {
+					self.incomplete = true;
+					self.add_token(TokenType::String(&self.source[self.start + 1..]));
+				} else if self.peek() != Some('"') {
+					self.error(ScanErrorKind::UnterminatedString, "Unterminated string.");
+				} else {
+					self.advance();
+					self.add_token(TokenType::String(
+						self.source[self.start + 1..self.current - 1].into(),
+					));
+				}
+            },
+            c => {
+				// number
+				if self.is_digit(c) {
+					while self.is_digit(self.peek()) {
+						self.advance();
+					}
+
+					if self.peek() == Some('.') && self.is_digit(self.peek_next()) {
+						self.advance();
+					}
+
+					while self.is_digit(self.peek()) {
+						self.advance();
+					}
+
+					// A typo like `123abc` is one bad literal, not a number
+					// followed by an identifier, so consume and report it whole.
+					if self.is_alpha(self.peek()) {
+						while self.is_alphanum(self.peek()) {
+							self.advance();
+						}
+						let literal = &self.source[self.start..self.current];
+						let message = format!(
+							"Invalid numeric literal '{}'.",
+							self.options.diagnostics.preview(literal)
+						);
+						self.error(ScanErrorKind::InvalidNumericLiteral, &message);
+						return;
+					}
+
+					let literal = &self.source[self.start..self.current];
+					if literal.len() > MAX_NUMBER_LENGTH {
+						let message = format!(
+							"Number literal is longer than {} characters.",
+							MAX_NUMBER_LENGTH
+						);
+						self.error(ScanErrorKind::NumberTooLong, &message);
+						return;
+					}
+
+					// Catch literals that round to infinity, or to zero despite
+					// having a nonzero digit.
+					let nonzero = literal.contains(|c: char| ('1'..='9').contains(&c));
+					match literal.parse::<f32>() {
+						Ok(f) if f.is_infinite() || (f == 0.0 && nonzero) => {
+							self.error(ScanErrorKind::NumberOutOfRange, "Number literal out of range.")
+						}
+						Ok(f) => self.add_token(TokenType::Number(f)),
+						Err(_) => self.error(ScanErrorKind::InvalidNumber, "Invalid number."),
+					}
+				}
+				// identifier
+				else if self.is_alphanum(c) {
+					while self.is_alphanum(self.peek()) {
+						self.advance();
+					}
+
+					let word = &self.source[self.start..self.current];
+					let lowered;
+					let word = if self.options.case_insensitive_keywords {
+						lowered = word.to_ascii_lowercase();
+						lowered.as_str()
+					} else {
+						word
+					};
+
+					match word {
+						"and" => self.add_token(TokenType::And),
+						"class" => self.add_token(TokenType::Class),
+						"else" => self.add_token(TokenType::Else),
+						"false" => self.add_token(TokenType::False),
+						"fun" => self.add_token(TokenType::Func),
+						"if" => self.add_token(TokenType::If),
+						"nil" => self.add_token(TokenType::Nil),
+						"or" => self.add_token(TokenType::Or),
+						"print" => self.add_token(TokenType::Print),
+						"return" => self.add_token(TokenType::Return),
+						"super" => self.add_token(TokenType::Super),
+						"this" => self.add_token(TokenType::This),
+						"true" => self.add_token(TokenType::True),
+						"var" => self.add_token(TokenType::Var),
+						"while" => self.add_token(TokenType::While),
+						_ => self.add_token(TokenType::Identifier),
+					}
+				}
+				else {
+					self.error(ScanErrorKind::UnexpectedCharacter, "Unexpected character.");
+				}
+			},
+        }
+    }
+
+    // Whether the line after the `\n` at `current` starts like a statement
+    // and has balanced quotes, so it cannot be the rest of an open string.
+    fn next_line_looks_like_code(&self) -> bool {
+        let rest = &self.source[self.current + 1..];
+        let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+        if !line.matches('"').count().is_multiple_of(2) {
+            return false;
+        }
+
+        let line = line.trim_start_matches([' ', '\t', '\r']);
+        if line.starts_with('}') {
+            return true;
+        }
+
+        let word_end = line
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(line.len());
+        matches!(
+            &line[..word_end],
+            "class" | "for" | "fun" | "if" | "print" | "return" | "var" | "while"
+        )
+    }
+
+    fn newline(&mut self) {
+        self.line += 1;
+    }
+
+    /// Starts line numbering at `line`, for scanning the rest of an input
+    /// whose earlier lines were scanned separately.
+    pub(crate) fn set_first_line(&mut self, line: usize) {
+        self.line = line;
+        self.first_line = line;
+    }
+
+    fn lines(&mut self) -> &SourceFile<'a> {
+        let source = self.source;
+        self.lines.get_or_insert_with(|| SourceFile::new("", source))
+    }
+
+    /// The line and column of the char at `offset`.
+    fn position(&mut self, offset: usize) -> (usize, usize) {
+        let (line, column) = self.lines().line_col(offset);
+        (line + self.first_line - 1, column)
+    }
+
+    /// The column just past the last char on the line containing `offset`.
+    fn line_end_column(&mut self, offset: usize) -> usize {
+        let lines = self.lines();
+        let (line, _) = lines.line_col(offset);
+        lines.line_text(line).chars().count() + 1
+    }
+
+    fn error(&mut self, kind: ScanErrorKind, message: &str) {
+        // Errors are placed where the offending text starts; text running
+        // onto later lines, like an unterminated string, is marked only up
+        // to the end of its first line.
+        let (line, column) = self.position(self.start);
+        let (end_line, end_column) = self.position(self.current);
+        let end_column = if end_line == line {
+            end_column
+        } else {
+            self.line_end_column(self.start)
+        };
+
+        // A closing quote right after the scanned text ends the string.
+        let fix_span = (kind == ScanErrorKind::UnterminatedString).then(|| {
+            let (line, column) = self.position(self.current);
+            FixSpan {
+                line,
+                column,
+                end_column: column,
+            }
+        });
+
+        self.errors.push(ScanError {
+            file: None,
+            line,
+            column,
+            end_column: end_column.max(column + 1),
+            kind,
+            message: message.to_string(),
+            fix_span,
+        });
+    }
+
+    pub fn tokens(&self) -> &[Token<'a>] {
+        &self.tokens
+    }
+
+    pub fn errors(&self) -> &[ScanError] {
+        &self.errors
+    }
+
+    /// Whether the source ended partway through a token. Only set with
+    /// `ScannerOptions::tolerate_incomplete`; the unfinished token is then
+    /// the last one scanned.
+    pub fn is_incomplete(&self) -> bool {
+        self.incomplete
+    }
+
+    pub fn scan_tokens(&mut self) {
+        while !self.is_at_end() {
+            self.start = self.current;
+            self.scan_token();
+        }
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.current >= self.source.len()
+    }
+
+    // `current` is a byte offset and always sits on a char boundary, so the
+    // lexeme slices taken from `start..current` are always valid.
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek();
+        if let Some(c) = c {
+            self.current += c.len_utf8();
+        }
+        c
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.current..].chars().next()
+    }
+
+	fn peek_next(&self) -> Option<char> {
+		let mut chars = self.source[self.current..].chars();
+		chars.next();
+		chars.next().or(Some('\0'))
+	}
+
+	fn is_digit(&self, val: Option<char>) -> bool {
+		match val {
+			Some('0') | Some('1') | Some('2') | Some('3') | Some('4') |
+			Some('5') | Some('6') | Some('7') | Some('8') | Some('9') => true,
+			None => false,
+			_ => false,
+		}
+	}
+
+	fn is_alpha(&self, val: Option<char>) -> bool {
+		match val {
+			Some(c) => {
+				c.is_ascii_lowercase() || c.is_ascii_uppercase() || c == '_'
+			},
+			None => false
+		}
+	}
+
+	fn is_alphanum(&self, val: Option<char>) -> bool {
+		self.is_digit(val) || self.is_alpha(val)
+	}
+
+    fn check(&mut self, expected: char) -> bool {
+        if self.is_at_end() {
+            return false;
+        }
+
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.current += c.len_utf8();
+                true
+            }
+            _ => false,
+        }
+    }
+}
@@ -1,371 +1,402 @@
 use std::env;
 use std::fs;
+use std::fs::File;
 use std::io;
-use std::io::Write;
-use std::path::Path;
+use std::io::IsTerminal;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::process::exit;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use jlox::config::{self, Config};
+use jlox::diagnostic::{json_string, JsonHandler};
+use jlox::highlight::highlight_html;
+use jlox::lex::{lex_full, FullTokenKind};
+use jlox::repl;
+use jlox::watch::{self, FsProbe, Watcher};
+use jlox::workspace;
+use jlox::{Features, Lox, Timings, VERSION};
+
+const USAGE: &str = "\
+Usage: jlox [options] [script...]
+
+Options:
+  --version               Print the version and enabled features
+  --diagnostics-json      Report diagnostics as JSON without running anything; takes
+                          files and directories, which are searched for *.lox files
+  --exclude <glob>        Skip matching files when searching directories
+  --highlight-html        Print a script as syntax-highlighted HTML
+  --tokens-json=full      Print every token, whitespace run, comment and error in a
+                          script as JSON, with byte offsets
+  --config <file>         Read settings from <file> instead of the nearest jlox.toml
+  --prompt <text>         REPL prompt (empty for none)
+  --quiet, --no-banner    Do not print the REPL banner
+  --separate              Run each script in its own interpreter
+  --max-errors <n>        Report at most <n> errors per script (0 for no limit)
+  --relaxed-keywords      Accept keywords in any case, such as Print or IF
+  --watch                 Re-run the scripts whenever they change
+  --watch-interval <ms>   How often --watch checks for changes
+  --time[=json]           Report how long each stage took to stderr after each script";
+
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy)]
+enum TimeFormat {
+    Table,
+    Json,
+}
 
-#[allow(dead_code)]
-#[derive(Debug)]
-pub enum TokenType<'a> {
-    // Single-character tokens.
-    LeftParen,
-    RightParen,
-    LeftBrace,
-    RightBrace,
-    Comma,
-    Dot,
-    Minus,
-    Plus,
-    Semicolon,
-    Slash,
-    Star,
-    // One or two character tokens.
-    Bang,
-    BangEqual,
-    Equal,
-    EqualEqual,
-    Greater,
-    GreaterEqual,
-    Less,
-    LessEqual,
-    // Literals.
-    Identifier,
-    String(&'a str),
-    Number(f32),
-    // Keywords.
-    And,
-    Class,
-    Else,
-    False,
-    Func,
-    For,
-    If,
-    Nil,
-    Or,
-    Print,
-    Return,
-    Super,
-    This,
-    True,
-    Var,
-    While,
-    Eof,
+enum Mode {
+    Run,
+    Check,
+    Highlight,
+    Tokens,
 }
 
-pub struct Lox {
-    had_error: bool,
+fn new_lox(config: &Config) -> Lox {
+    let mut lox = Lox::new();
+    lox.set_scanner_options(config.scanner.clone());
+    lox
 }
 
-impl Lox {
-    pub fn new() -> Self {
-        Lox { had_error: false }
+/// Runs one script in `lox`, failing with the exit code it warrants.
+fn run_file(lox: &mut Lox, file_path: &Path) -> Result<(), i32> {
+    let read_failed = |e: io::Error| {
+        eprintln!("error: could not read {}: {}", file_path.display(), e);
+        74
+    };
+    let file = File::open(file_path).map_err(read_failed)?;
+
+    lox.set_source_name(Some(file_path.display().to_string()));
+    lox.run_reader(BufReader::new(file));
+    if lox.had_internal_error {
+        return Err(71);
     }
-
-    pub fn run(&self, source: &str) {
-        let mut scanner = Scanner::new(source);
-        scanner.scan_tokens();
-
-        for token in scanner.tokens {
-            println!("{:?}", token);
-        }
+    if let Some(e) = lox.read_error.take() {
+        return Err(read_failed(e));
     }
-}
-
-#[derive(Debug)]
-pub struct Token<'a> {
-    token_type: TokenType<'a>,
-    lexeme: String,
-    line: usize,
-}
-
-impl<'a> Token<'a> {
-    pub fn new(token_type: TokenType<'a>, lexeme: String, line: usize) -> Self {
-        Token {
-            token_type,
-            lexeme,
-            line,
-        }
+    if let Some(e) = lox.output_error.take() {
+        eprintln!("error: {}", e);
+        return Err(74);
     }
-
-    pub fn to_string(&self) -> String {
-        format!("{:?} {} {}", self.token_type, self.lexeme, self.line)
+    if lox.had_error {
+        lox.had_error = false;
+        return Err(65);
     }
+    Ok(())
 }
 
-pub struct Scanner<'a> {
-    source: &'a str,
-    tokens: Vec<Token<'a>>,
-    start: usize,
-    current: usize,
-    line: usize,
+fn report_timings(script: &str, timings: &Timings, format: TimeFormat) {
+    let scan_ms = timings.scan.as_secs_f64() * 1000.0;
+    match format {
+        TimeFormat::Table => {
+            eprintln!("--- timings for {} ---", script);
+            eprintln!("scan      {:>10.3} ms", scan_ms);
+            eprintln!("tokens    {:>10}", timings.tokens);
+        }
+        TimeFormat::Json => eprintln!(
+            "{{\"file\":{},\"scanMs\":{:.3},\"tokens\":{}}}",
+            json_string(script),
+            scan_ms,
+            timings.tokens
+        ),
+    }
 }
 
-impl<'a> Scanner<'a> {
-    pub fn new(source: &'a str) -> Self {
-        Scanner {
-            source,
-            tokens: Vec::new(),
-            start: 0,
-            current: 0,
-            line: 0,
+/// Runs the scripts in order in one shared interpreter, stopping at the
+/// first that fails, or with `separate` in a fresh interpreter each,
+/// running them all. With `time`, each script's timings follow its run.
+fn run_files(
+    config: &Config,
+    scripts: &[String],
+    separate: bool,
+    time: Option<TimeFormat>,
+) -> Result<(), i32> {
+    let mut lox = new_lox(config);
+    let mut result = Ok(());
+
+    for script in scripts {
+        if separate {
+            lox = new_lox(config);
+        }
+        let outcome = run_file(&mut lox, Path::new(script));
+        if let Some(format) = time {
+            report_timings(script, &lox.take_timings(), format);
+        }
+        if let Err(code) = outcome {
+            result = result.and(Err(code));
+            if !separate {
+                break;
+            }
         }
     }
 
-    pub fn add_token(&mut self, token_type: TokenType<'a>) {
-        let token = Token::new(
-            token_type,
-            self.source[self.start..self.current].into(),
-            self.line,
-        );
+    result
+}
 
-        self.tokens.push(token);
-    }
+/// Runs the scripts, then again each time they change, until killed.
+fn watch_files(
+    config: &Config,
+    scripts: &[String],
+    separate: bool,
+    time: Option<TimeFormat>,
+    interval: Duration,
+) -> ! {
+    let paths = scripts.iter().map(PathBuf::from).collect();
+    let mut watcher = Watcher::new(paths, FsProbe, WATCH_DEBOUNCE);
 
-    pub fn add_literal(&mut self, token_type: TokenType<'a>) {
-        let token = Token::new(
-            token_type,
-            self.source[self.start..self.current].into(),
-            self.line,
+    loop {
+        println!(
+            "--- [{}] running {} ---",
+            watch::timestamp(SystemTime::now()),
+            scripts.join(" ")
         );
+        if let Err(code) = run_files(config, scripts, separate, time) {
+            println!("--- failed with exit code {} ---", code);
+        }
 
-        self.tokens.push(token);
+        while !watcher.poll(Instant::now()) {
+            thread::sleep(interval);
+        }
     }
+}
 
-    pub fn scan_token(&mut self) {
-        match self.advance() {
-            Some('(') => self.add_token(TokenType::LeftParen),
-            Some(')') => self.add_token(TokenType::RightParen),
-            Some('{') => self.add_token(TokenType::LeftBrace),
-            Some('}') => self.add_token(TokenType::RightBrace),
-            Some(',') => self.add_token(TokenType::Comma),
-            Some('.') => self.add_token(TokenType::Dot),
-            Some('-') => self.add_token(TokenType::Minus),
-            Some('+') => self.add_token(TokenType::Plus),
-            Some(';') => self.add_token(TokenType::Semicolon),
-            Some('*') => self.add_token(TokenType::Star),
-            Some('\n') => {
-                self.line += 1;
-            },
-            Some(' ') | Some('\r') | Some('\t') => (),
-            Some('/') => {
-                if self.check('/') {
-                    while (self.peek() != Some('\n')) && !self.is_at_end() {
-                        self.advance();
-                    }
-                } else {
-                    self.add_token(TokenType::Slash);
-                }
-            }
-            Some('!') => {
-                if self.check('=') {
-                    self.add_token(TokenType::BangEqual)
-                } else {
-                    self.add_token(TokenType::Bang)
-                }
-            }
-            Some('=') => {
-                if self.check('=') {
-                    self.add_token(TokenType::EqualEqual)
-                } else {
-                    self.add_token(TokenType::Equal)
-                }
-            }
-            Some('<') => {
-                if self.check('=') {
-                    self.add_token(TokenType::LessEqual)
-                } else {
-                    self.add_token(TokenType::Less)
-                }
-            }
-            Some('>') => {
-                if self.check('=') {
-                    self.add_token(TokenType::GreaterEqual)
-                } else {
-                    self.add_token(TokenType::Greater)
-                }
-            }
-            Some('"') => {
-				while (self.peek() != Some('"')) && !self.is_at_end() {
-					if self.peek() == Some('\n') {
-						self.line += 1;
-					}
-					self.advance();
-				}
-
-				if !self.is_at_end() {
-					self.advance();
-					self.add_token(TokenType::String(
-						self.source[self.start + 1..self.current - 1].into(),
-					));
-				}
-            },
-            c => {
-				// number
-				if self.is_digit(c) {
-					while self.is_digit(self.peek()) {
-						self.advance();
-					}
-
-					if self.peek() == Some('.') && self.is_digit(self.peek_next()) {
-						self.advance();
-					}
-
-					while self.is_digit(self.peek()) {
-						self.advance();
-					}
-
-					match self.source[self.start..self.current].parse::<f32>() {
-						Ok(f) => self.add_token(TokenType::Number(f)),
-						Err(_) => exit(65),
-					}
-				}
-				// identifier
-				else if self.is_alphanum(c) {
-					while self.is_alphanum(self.peek()) {
-						self.advance();
-					}
-
-					match self.source[self.start..self.current].into() {
-						"and" => self.add_token(TokenType::And),
-						"class" => self.add_token(TokenType::Class),
-						"else" => self.add_token(TokenType::Else),
-						"false" => self.add_token(TokenType::False),
-						"fun" => self.add_token(TokenType::Func),
-						"if" => self.add_token(TokenType::If),
-						"nil" => self.add_token(TokenType::Nil),
-						"or" => self.add_token(TokenType::Or),
-						"print" => self.add_token(TokenType::Print),
-						"return" => self.add_token(TokenType::Return),
-						"super" => self.add_token(TokenType::Super),
-						"this" => self.add_token(TokenType::This),
-						"true" => self.add_token(TokenType::True),
-						"var" => self.add_token(TokenType::Var),
-						"while" => self.add_token(TokenType::While),
-						_ => self.add_token(TokenType::Identifier),
-					}
-				}
-				else {
-					println!("Unexpected character. {:?}, {}", c, self.line);
-				}
-			},
+/// Reports diagnostics for every Lox file under `paths` without running
+/// them, exiting with a failure code if any file had errors.
+fn check_files(config: &Config, paths: &[String], excludes: &[String]) {
+    let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+    let files = match workspace::collect_files(&paths, excludes) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            exit(74);
         }
-    }
+    };
 
-    pub fn scan_tokens(&mut self) {
-        while !self.is_at_end() {
-            self.start = self.current;
-            self.scan_token();
+    let mut result = Ok(());
+    for file in files {
+        if let Err(code) = check_file(config, &file) {
+            result = result.and(Err(code));
         }
     }
 
-    fn is_at_end(&self) -> bool {
-        self.current >= self.source.len() as usize
+    if let Err(code) = result {
+        exit(code);
     }
+}
+
+fn check_file(config: &Config, file_path: &Path) -> Result<(), i32> {
+    let source = fs::read_to_string(file_path).map_err(|_| 64)?;
 
-    fn advance(&mut self) -> Option<char> {
-        let c = self.source.chars().nth(self.current);
-        self.current += 1;
-        return c;
+    let mut lox = Lox::with_handler(Box::new(JsonHandler));
+    lox.set_scanner_options(config.scanner.clone());
+    lox.set_source_name(Some(file_path.display().to_string()));
+    lox.check(&source);
+    if lox.had_internal_error {
+        return Err(71);
     }
+    if lox.had_error {
+        return Err(65);
+    }
+    Ok(())
+}
 
-    fn peek(&self) -> Option<char> {
-        if self.is_at_end() {
-            return None;
-        } else {
-            return self.source.chars().nth(self.current);
+/// Writes to stdout with `write`, stopping quietly if the reader goes away
+/// (as `head` does) and exiting on any other failure.
+fn write_stdout(write: impl FnOnce(&mut dyn Write) -> io::Result<()>) {
+    let mut out = BufWriter::new(io::stdout().lock());
+    match write(&mut out).and_then(|()| out.flush()) {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+            eprintln!("error: {}", e);
+            exit(74);
         }
+        _ => (),
     }
+}
+
+fn highlight_file(file_path: &Path) {
+    let source = fs::read_to_string(file_path).unwrap_or_else(|_| exit(64));
+    write_stdout(|out| write!(out, "{}", highlight_html(&source)));
+}
 
-	fn peek_next(&self) -> Option<char> {
-		if self.current + 1 >= self.source.len() {
-			return Some('\0');
-		} else {
-			return self.source.chars().nth(self.current + 1);
-		}
-	}
-
-	fn is_digit(&self, val: Option<char>) -> bool {
-		match val {
-			Some('0') | Some('1') | Some('2') | Some('3') | Some('4') |
-			Some('5') | Some('6') | Some('7') | Some('8') | Some('9') => true,
-			None => false,
-			_ => false,
-		}
-	}
-
-	fn is_alpha(&self, val: Option<char>) -> bool {
-		match val {
-			Some(c) => {
-				(c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z' || c == '_')
-			},
-			None => false
-		}
-	}
-
-	fn is_alphanum(&self, val: Option<char>) -> bool {
-		self.is_digit(val) || self.is_alpha(val)
-	}
-
-    fn check(&mut self, expected: char) -> bool {
-        if self.is_at_end() {
-            return false;
+/// Prints the script as a JSON array with one entry per line, covering
+/// every byte of it.
+fn print_tokens_json(file_path: &Path) {
+    let source = fs::read_to_string(file_path).unwrap_or_else(|_| exit(64));
+    let entries = lex_full(&source);
+
+    write_stdout(|out| {
+        writeln!(out, "[")?;
+        for (i, entry) in entries.iter().enumerate() {
+            let (kind, name) = match entry.kind {
+                FullTokenKind::Token(token_type) => ("token", Some(token_type.name())),
+                FullTokenKind::Whitespace => ("whitespace", None),
+                FullTokenKind::Comment => ("comment", None),
+                FullTokenKind::Error(kind) => ("error", Some(kind.code())),
+            };
+            writeln!(
+                out,
+                "  {{\"kind\":\"{}\",\"type\":{},\"line\":{},\"start\":{},\"end\":{},\"text\":{}}}{}",
+                kind,
+                name.map_or("null".to_string(), json_string),
+                entry.line,
+                entry.span.start,
+                entry.span.end,
+                json_string(entry.text),
+                if i + 1 < entries.len() { "," } else { "" }
+            )?;
         }
+        writeln!(out, "]")
+    });
+}
+
+fn run_prompt(config: &Config) {
+    let mut lox = new_lox(config);
+    let stdin = io::stdin().lock();
 
-        match self.source.chars().nth(self.current) {
-            Some(c) => {
-                if c == expected {
-                    self.current += 1;
-                    true
+    // Keep captured output limited to program results when stdout is piped.
+    let result = if io::stdout().is_terminal() {
+        repl::run(&mut lox, &config.repl, stdin, io::stdout())
+    } else {
+        repl::run(&mut lox, &config.repl, stdin, io::stderr())
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        exit(74);
+    }
+}
+
+/// Loads the explicit settings file, or else the nearest `jlox.toml` above
+/// the first script (or the working directory when there is none).
+fn load_config(explicit: Option<PathBuf>, script: Option<&String>) -> Config {
+    let path = explicit.or_else(|| {
+        let dir = match script {
+            // A directory of scripts is searched from itself, a script from
+            // the directory it is in.
+            Some(script) => {
+                let path = Path::new(script).canonicalize().ok()?;
+                if path.is_dir() {
+                    path
                 } else {
-                    false
+                    path.parent()?.to_path_buf()
                 }
             }
-            None => false,
+            None => env::current_dir().ok()?,
+        };
+        config::discover(&dir)
+    });
+
+    let path = match path {
+        Some(path) => path,
+        None => return Config::default(),
+    };
+
+    match Config::load(&path) {
+        Ok((config, warnings)) => {
+            for warning in warnings {
+                eprintln!("warning: {}: {}", path.display(), warning);
+            }
+            config
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            exit(64);
         }
     }
 }
 
-fn run_file(file_path: &Path) {
-    match fs::read_to_string(file_path) {
-        Ok(s) => {
-            let lox = Lox::new();
-            lox.run(&s);
-            if lox.had_error {
-                exit(65);
-            }
-        }
-        Err(_) => exit(64),
+fn print_version() {
+    let features = Features::enabled().names();
+    println!("jlox {}", VERSION);
+    if features.is_empty() {
+        println!("features: none");
+    } else {
+        println!("features: {}", features.join(", "));
     }
 }
 
-fn run_prompt() {
-    let mut lox = Lox::new();
+fn usage() -> ! {
+    println!("{}", USAGE);
+    exit(64);
+}
 
-    loop {
-        let mut buffer = String::new();
-        let stdin = io::stdin();
-        print!(">> ");
-        io::stdout().flush().unwrap();
-        match stdin.read_line(&mut buffer) {
-            Ok(0) | Err(_) => break,
-            _ => (),
+fn main() {
+    let mut mode = Mode::Run;
+    let mut scripts = Vec::new();
+    let mut separate = false;
+    let mut watch = false;
+    let mut watch_interval = WATCH_INTERVAL;
+    let mut config_path = None;
+    let mut prompt = None;
+    let mut banner = None;
+    let mut relaxed_keywords = false;
+    let mut excludes = Vec::new();
+    let mut time = None;
+    let mut max_errors = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--version" => {
+                print_version();
+                return;
+            }
+            "--diagnostics-json" => mode = Mode::Check,
+            "--highlight-html" => mode = Mode::Highlight,
+            "--tokens-json=full" => mode = Mode::Tokens,
+            "--config" => config_path = Some(PathBuf::from(args.next().unwrap_or_else(|| usage()))),
+            "--prompt" => prompt = Some(args.next().unwrap_or_else(|| usage())),
+            "--quiet" | "--no-banner" => banner = Some(false),
+            "--separate" => separate = true,
+            "--relaxed-keywords" => relaxed_keywords = true,
+            "--max-errors" => {
+                let n = args.next().and_then(|n| n.parse().ok());
+                max_errors = Some(n.unwrap_or_else(|| usage()));
+            }
+            "--exclude" => excludes.push(args.next().unwrap_or_else(|| usage())),
+            "--watch" => watch = true,
+            "--time" => time = Some(TimeFormat::Table),
+            "--time=json" => time = Some(TimeFormat::Json),
+            "--watch-interval" => {
+                let ms = args.next().and_then(|ms| ms.parse().ok());
+                watch_interval = Duration::from_millis(ms.unwrap_or_else(|| usage()));
+            }
+            _ if !arg.starts_with("--") => scripts.push(arg),
+            _ => usage(),
         }
-
-        lox.run(&buffer);
-        lox.had_error = false;
     }
-}
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+    // Settings files apply first so that flags on the command line win.
+    let mut config = load_config(config_path, scripts.first());
+    if let Some(prompt) = prompt {
+        config.repl.prompt = prompt;
+    }
+    if let Some(banner) = banner {
+        config.repl.banner = banner;
+    }
+    if let Some(max_errors) = max_errors {
+        config.scanner.diagnostics.max_errors = max_errors;
+    }
+    if relaxed_keywords {
+        config.scanner.case_insensitive_keywords = true;
+    }
 
-    if args.len() == 1 {
-        run_prompt();
-    } else if args.len() == 2 {
-        run_file(&Path::new(&args[2]));
-    } else {
-        println!("Usage: jlox [script]");
-        exit(64);
+    match (mode, scripts.as_slice()) {
+        (Mode::Run, []) => run_prompt(&config),
+        (Mode::Run, scripts) if watch => {
+            watch_files(&config, scripts, separate, time, watch_interval)
+        }
+        (Mode::Run, scripts) => {
+            if let Err(code) = run_files(&config, scripts, separate, time) {
+                exit(code);
+            }
+        }
+        (Mode::Check, paths) if !paths.is_empty() => check_files(&config, paths, &excludes),
+        (Mode::Highlight, [script]) => highlight_file(Path::new(script)),
+        (Mode::Tokens, [script]) => print_tokens_json(Path::new(script)),
+        _ => usage(),
     }
 }
@@ -0,0 +1,113 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{Lox, Scanner, ScannerOptions, TokenType, VERSION};
+
+/// Shown instead of the prompt while an entry spans several lines.
+const CONTINUATION_PROMPT: &str = "... ";
+
+/// How the interactive prompt presents itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplConfig {
+    /// Written before each line is read; empty disables the prompt.
+    pub prompt: String,
+    /// Whether to greet the user with the interpreter version on startup.
+    pub banner: bool,
+}
+
+impl Default for ReplConfig {
+    fn default() -> Self {
+        ReplConfig {
+            prompt: ">> ".to_string(),
+            banner: true,
+        }
+    }
+}
+
+/// Reads entries from `input` and runs each one until end of input.
+///
+/// An entry is usually one line, but continues onto the next while it has
+/// an open string or more opening brackets than closing ones. Errors in an
+/// entry are reported and the session carries on; it ends cleanly at end of
+/// input, running any unfinished entry first, and fails only if `input`
+/// cannot be read or program output cannot be written.
+///
+/// The banner and prompt go to `prompt_out`, which is flushed before every
+/// read so the prompt is visible even when it is a pipe. Program output is
+/// unaffected, so callers can keep it apart from the prompt by passing
+/// stderr here.
+pub fn run<R: BufRead, W: Write>(
+    lox: &mut Lox,
+    config: &ReplConfig,
+    mut input: R,
+    mut prompt_out: W,
+) -> io::Result<()> {
+    if config.banner {
+        writeln!(prompt_out, "jlox {}", VERSION)?;
+    }
+
+    let mut buffer = String::new();
+    loop {
+        if !config.prompt.is_empty() {
+            let prompt = if buffer.is_empty() {
+                config.prompt.as_str()
+            } else {
+                CONTINUATION_PROMPT
+            };
+            write!(prompt_out, "{}", prompt)?;
+            prompt_out.flush()?;
+        }
+
+        let at_end = loop {
+            match input.read_line(&mut buffer) {
+                Ok(read) => break read == 0,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        };
+        if at_end && buffer.is_empty() {
+            break;
+        }
+        if !at_end && is_unfinished(&buffer) {
+            continue;
+        }
+
+        lox.run(&buffer);
+        if let Some(e) = lox.output_error.take() {
+            return Err(e);
+        }
+        buffer.clear();
+        lox.had_error = false;
+        lox.had_internal_error = false;
+    }
+
+    // End of input usually means Ctrl-D at the prompt; finish the line so
+    // the shell's prompt does not start after ours.
+    if !config.prompt.is_empty() {
+        writeln!(prompt_out)?;
+    }
+    Ok(())
+}
+
+/// Whether `source` stops partway through an entry that the next line
+/// could finish.
+fn is_unfinished(source: &str) -> bool {
+    let options = ScannerOptions {
+        tolerate_incomplete: true,
+        ..ScannerOptions::default()
+    };
+    let mut scanner = Scanner::with_options(source, options);
+    scanner.scan_tokens();
+    if scanner.is_incomplete() {
+        return true;
+    }
+
+    let mut depth = 0i32;
+    for token in scanner.tokens() {
+        match token.token_type() {
+            TokenType::LeftParen | TokenType::LeftBrace => depth += 1,
+            TokenType::RightParen | TokenType::RightBrace => depth -= 1,
+            _ => (),
+        }
+    }
+    depth > 0
+}
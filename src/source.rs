@@ -0,0 +1,111 @@
+//! Mapping between byte offsets and line/column positions in a source file.
+//!
+//! Offsets count bytes from the start of the file and always sit on char
+//! boundaries. Lines and columns start at 1, and columns count chars, as in
+//! `ScanError`. A line ends at its `\n`; a `\r` before it still counts as a
+//! column, but is left out of `line_text` and `line_span`.
+
+use std::borrow::Cow;
+
+use crate::Span;
+
+/// A named source text, borrowed or owned, with its line starts indexed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceFile<'a> {
+    name: String,
+    text: Cow<'a, str>,
+    // Byte offset where each line begins; the first is always 0.
+    line_starts: Vec<usize>,
+}
+
+impl<'a> SourceFile<'a> {
+    pub fn new(name: impl Into<String>, text: impl Into<Cow<'a, str>>) -> Self {
+        let text = text.into();
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        SourceFile {
+            name: name.into(),
+            text,
+            line_starts,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The number of lines, counting the text after the last line break as
+    /// a line even when it is empty.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// The line and column of the char at `offset`, which may also be the
+    /// length of the text for the position just past its end.
+    ///
+    /// # Panics
+    ///
+    /// If `offset` is past the end of the text or not on a char boundary.
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        assert!(
+            self.text.is_char_boundary(offset),
+            "offset {} is not a position in {}",
+            offset,
+            self.name
+        );
+        // The number of lines starting at or before `offset`.
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let start = self.line_starts[line - 1];
+        (line, self.text[start..offset].chars().count() + 1)
+    }
+
+    /// The byte offset of `line` and `column`, or `None` if the line does
+    /// not exist or is too short. The column just past a line's last char,
+    /// where its line break is, is valid.
+    pub fn offset(&self, line: usize, column: usize) -> Option<usize> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self.line_end(line);
+        let column = column.checked_sub(1)?;
+
+        match self.text[start..end].char_indices().nth(column) {
+            Some((i, _)) => Some(start + i),
+            None if self.text[start..end].chars().count() == column => Some(end),
+            None => None,
+        }
+    }
+
+    /// The text of `line` without its line break.
+    ///
+    /// # Panics
+    ///
+    /// If the line does not exist.
+    pub fn line_text(&self, line: usize) -> &str {
+        &self.text[self.line_span(line).range()]
+    }
+
+    /// The bytes of `line`, without its line break.
+    ///
+    /// # Panics
+    ///
+    /// If the line does not exist.
+    pub fn line_span(&self, line: usize) -> Span {
+        let start = self.line_starts[line - 1];
+        let end = self.line_end(line);
+        let text = &self.text[start..end];
+        Span::new(start, start + text.strip_suffix('\r').unwrap_or(text).len())
+    }
+
+    // Where `line` ends, before its `\n` but after any `\r`.
+    fn line_end(&self, line: usize) -> usize {
+        match self.line_starts.get(line) {
+            Some(next) => next - 1,
+            None => self.text.len(),
+        }
+    }
+}
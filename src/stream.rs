@@ -0,0 +1,222 @@
+use std::collections::VecDeque;
+use std::io::{self, ErrorKind, Read};
+use std::str;
+
+use crate::diagnostic::ScanError;
+use crate::{Scanner, ScannerOptions, Span, Token, TokenType};
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// A token that owns its text, so it can outlive the scanner's buffer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnedToken {
+    // String literal payloads are recovered from the lexeme in `as_token`.
+    token_type: TokenType<'static>,
+    lexeme: String,
+    line: usize,
+    span: Span,
+}
+
+impl OwnedToken {
+    /// Copies `token`, shifting its span by `offset` bytes to account for
+    /// input already dropped from the buffer.
+    fn from_token(token: &Token, offset: usize) -> Self {
+        OwnedToken {
+            token_type: token.token_type.map_str(|_| ""),
+            lexeme: token.lexeme.to_string(),
+            line: token.line,
+            span: Span::new(token.span.start + offset, token.span.end + offset),
+        }
+    }
+
+    pub fn as_token(&self) -> Token<'_> {
+        // An incomplete string (see `tolerate_incomplete`) has no closing quote.
+        let literal = |_| {
+            let body = &self.lexeme[1..];
+            body.strip_suffix('"').unwrap_or(body)
+        };
+        Token::new(self.token_type.map_str(literal), &self.lexeme, self.line, self.span)
+    }
+}
+
+/// Scans tokens from any `io::Read` source using a sliding buffer.
+///
+/// Only the text of the token currently being scanned (plus one read chunk)
+/// is kept in memory, so input size is bounded by the longest token rather
+/// than by the length of the file.
+pub struct StreamingScanner<R> {
+    reader: R,
+    options: ScannerOptions,
+    buffer: String,
+    // Bytes read but not yet decoded because they end mid-character.
+    pending: Vec<u8>,
+    queue: VecDeque<Result<OwnedToken, ScanError>>,
+    line: usize,
+    // Characters of the current line already drained from the buffer.
+    column_carry: usize,
+    // Bytes of input already drained from the buffer.
+    offset: usize,
+    eof: bool,
+    // Why reading stopped early, if it did.
+    read_error: Option<io::Error>,
+}
+
+impl<R: Read> StreamingScanner<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ScannerOptions::default())
+    }
+
+    pub fn with_options(reader: R, options: ScannerOptions) -> Self {
+        StreamingScanner {
+            reader,
+            options,
+            buffer: String::new(),
+            pending: Vec::new(),
+            queue: VecDeque::new(),
+            line: 1,
+            column_carry: 0,
+            offset: 0,
+            eof: false,
+            read_error: None,
+        }
+    }
+
+    /// The error that stopped reading, if any. Input read before it is still
+    /// scanned, as though the source ended there.
+    pub fn take_read_error(&mut self) -> Option<io::Error> {
+        self.read_error.take()
+    }
+
+    fn fill(&mut self) {
+        let mut chunk = [0; CHUNK_SIZE];
+        let read = loop {
+            match self.reader.read(&mut chunk) {
+                Ok(n) => break n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.read_error = Some(e);
+                    break 0;
+                }
+            }
+        };
+
+        if read == 0 {
+            self.eof = true;
+        }
+        self.pending.extend_from_slice(&chunk[..read]);
+        self.decode();
+    }
+
+    fn decode(&mut self) {
+        loop {
+            match str::from_utf8(&self.pending) {
+                Ok(text) => {
+                    self.buffer.push_str(text);
+                    self.pending.clear();
+                    return;
+                }
+                Err(e) => {
+                    let valid = e.valid_up_to();
+                    // Safe to unwrap: the prefix was just validated.
+                    self.buffer
+                        .push_str(str::from_utf8(&self.pending[..valid]).unwrap());
+                    match e.error_len() {
+                        Some(len) => {
+                            self.buffer.push(char::REPLACEMENT_CHARACTER);
+                            self.pending.drain(..valid + len);
+                        }
+                        None if self.eof => {
+                            self.buffer.push(char::REPLACEMENT_CHARACTER);
+                            self.pending.clear();
+                            return;
+                        }
+                        None => {
+                            self.pending.drain(..valid);
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Scans every token that is known to be complete in the buffer.
+    ///
+    /// A token is only complete once the scanner's lookahead (at most two
+    /// characters) stays inside the buffer; anything closer to the end might
+    /// continue in the next chunk, so it is rescanned after a refill.
+    fn scan_buffered(&mut self) {
+        let mut scanner = Scanner::with_options(&self.buffer, self.options.clone());
+        scanner.set_first_line(self.line);
+        let first_line = self.line;
+        let mut consumed = 0;
+
+        while !scanner.is_at_end() {
+            scanner.start = scanner.current;
+            let errors = scanner.errors.len();
+            scanner.scan_token();
+
+            if !self.eof && scanner.current + 1 >= self.buffer.len() {
+                break;
+            }
+            // Whether a string ends at a line break depends on the whole of
+            // the line after it, so a string that was cut short, or that went
+            // on past a line break, is only final once that line is buffered.
+            if !self.eof && self.buffer[scanner.start..].starts_with('"') {
+                let cut = scanner.errors.len() > errors;
+                if cut || self.buffer[scanner.start..scanner.current].contains('\n') {
+                    let rest = &self.buffer[scanner.current..];
+                    let rest = if cut { &rest[1..] } else { rest };
+                    if !rest.contains('\n') {
+                        break;
+                    }
+                }
+            }
+
+            for token in scanner.tokens.drain(..) {
+                self.queue.push_back(Ok(OwnedToken::from_token(&token, self.offset)));
+            }
+            for mut error in scanner.errors.drain(..) {
+                if error.line == first_line && error.column != 0 {
+                    error.column += self.column_carry;
+                    error.end_column += self.column_carry;
+                }
+                if let Some(fix) = error.fix_span.as_mut().filter(|fix| fix.line == first_line) {
+                    fix.column += self.column_carry;
+                    fix.end_column += self.column_carry;
+                }
+                self.queue.push_back(Err(error));
+            }
+            consumed = scanner.current.min(self.buffer.len());
+            self.line = scanner.line;
+        }
+
+        let line_start = self.buffer[..consumed].rfind('\n').map_or(0, |i| i + 1);
+        let rest_of_line = self.buffer[line_start..consumed].chars().count();
+        if self.line == first_line {
+            self.column_carry += rest_of_line;
+        } else {
+            self.column_carry = rest_of_line;
+        }
+        self.buffer.drain(..consumed);
+        self.offset += consumed;
+    }
+}
+
+impl<R: Read> Iterator for StreamingScanner<R> {
+    type Item = Result<OwnedToken, ScanError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.queue.is_empty() {
+            if self.eof && self.buffer.is_empty() {
+                return None;
+            }
+            if !self.eof {
+                self.fill();
+            }
+            self.scan_buffered();
+        }
+
+        self.queue.pop_front()
+    }
+}
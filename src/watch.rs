@@ -0,0 +1,82 @@
+//! Polling file watcher behind `jlox --watch`.
+//!
+//! Change detection compares modification times rather than relying on
+//! platform notification APIs. The filesystem and the clock are both
+//! supplied by the caller, so the logic can be driven without real files
+//! or sleeps.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Reports when a file was last modified.
+pub trait Probe {
+    fn modified(&mut self, path: &Path) -> Option<SystemTime>;
+}
+
+/// Reads modification times from the real filesystem.
+#[derive(Debug, Default)]
+pub struct FsProbe;
+
+impl Probe for FsProbe {
+    fn modified(&mut self, path: &Path) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+}
+
+pub struct Watcher<P> {
+    paths: Vec<PathBuf>,
+    stamps: Vec<Option<SystemTime>>,
+    probe: P,
+    debounce: Duration,
+    // When the files were last seen changing, if a re-run is pending.
+    changed_at: Option<Instant>,
+}
+
+impl<P: Probe> Watcher<P> {
+    /// Starts watching `paths` from their current state. A change is only
+    /// reported once the files have stayed untouched for `debounce`, so an
+    /// editor saving several times in a row triggers a single re-run.
+    pub fn new(paths: Vec<PathBuf>, mut probe: P, debounce: Duration) -> Self {
+        let stamps = paths.iter().map(|p| probe.modified(p)).collect();
+        Watcher {
+            paths,
+            stamps,
+            probe,
+            debounce,
+            changed_at: None,
+        }
+    }
+
+    /// Checks the files at time `now`, returning whether they should be
+    /// re-run.
+    pub fn poll(&mut self, now: Instant) -> bool {
+        let stamps: Vec<_> = self.paths.iter().map(|p| self.probe.modified(p)).collect();
+        if stamps != self.stamps {
+            self.stamps = stamps;
+            self.changed_at = Some(now);
+            return false;
+        }
+
+        match self.changed_at {
+            Some(at) if now.duration_since(at) >= self.debounce => {
+                self.changed_at = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Formats a wall-clock time as `HH:MM:SS` UTC for run headers.
+pub fn timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    format!(
+        "{:02}:{:02}:{:02}",
+        secs / 3600 % 24,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
@@ -0,0 +1,174 @@
+//! Expanding command-line paths into the Lox files they cover.
+//!
+//! Directories are searched recursively for `*.lox` files, skipping anything
+//! matched by `--exclude` patterns or by a `.loxignore` file at the root of
+//! the directory being searched. Ignore files use a small subset of
+//! gitignore syntax:
+//!
+//! - blank lines and lines starting with `#` are skipped;
+//! - a trailing `/` makes a pattern match directories only;
+//! - a pattern containing any other `/` is matched against the path
+//!   relative to the root, otherwise against each file or directory name;
+//! - `*` matches within one path component, `?` matches one character,
+//!   and `**` matches any number of components.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const IGNORE_FILE_NAME: &str = ".loxignore";
+
+#[derive(Debug, Clone, PartialEq)]
+struct Pattern {
+    segments: Vec<String>,
+    dir_only: bool,
+    anchored: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IgnoreRules {
+    patterns: Vec<Pattern>,
+}
+
+impl IgnoreRules {
+    /// Reads rules from the text of an ignore file.
+    pub fn parse(text: &str) -> Self {
+        let mut rules = IgnoreRules::default();
+        for line in text.lines() {
+            let line = line.trim();
+            if !line.is_empty() && !line.starts_with('#') {
+                rules.add(line);
+            }
+        }
+        rules
+    }
+
+    pub fn add(&mut self, pattern: &str) {
+        let dir_only = pattern.ends_with('/');
+        let pattern = pattern.trim_end_matches('/');
+        let anchored = pattern.contains('/');
+        let segments = pattern
+            .trim_start_matches('/')
+            .split('/')
+            .map(str::to_string)
+            .collect();
+
+        self.patterns.push(Pattern {
+            segments,
+            dir_only,
+            anchored,
+        });
+    }
+
+    /// Whether `path`, relative to the root the rules apply to, is ignored.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let components: Vec<String> = path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        let name = match components.last() {
+            Some(name) => name,
+            None => return false,
+        };
+
+        self.patterns.iter().any(|pattern| {
+            if pattern.dir_only && !is_dir {
+                return false;
+            }
+            if pattern.anchored {
+                match_segments(&pattern.segments, &components)
+            } else {
+                match_glob(&pattern.segments[0], name)
+            }
+        })
+    }
+}
+
+/// Expands `paths` into the files to process, in a stable order.
+///
+/// Files named directly are always included; directories contribute the
+/// `*.lox` files beneath them that neither `excludes` nor the directory's
+/// own ignore file rule out.
+pub fn collect_files(paths: &[PathBuf], excludes: &[String]) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+
+    for path in paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+
+        let mut rules = match fs::read_to_string(path.join(IGNORE_FILE_NAME)) {
+            Ok(text) => IgnoreRules::parse(&text),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => IgnoreRules::default(),
+            Err(e) => return Err(e),
+        };
+        for exclude in excludes {
+            rules.add(exclude);
+        }
+
+        walk(path, Path::new(""), &rules, &mut files)?;
+    }
+
+    Ok(files)
+}
+
+fn walk(root: &Path, relative: &Path, rules: &IgnoreRules, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = fs::read_dir(root.join(relative))?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let relative = relative.join(entry.file_name());
+        let is_dir = entry.file_type()?.is_dir();
+        if rules.is_ignored(&relative, is_dir) {
+            continue;
+        }
+
+        if is_dir {
+            walk(root, &relative, rules, files)?;
+        } else if relative.extension().is_some_and(|ext| ext == "lox") {
+            files.push(root.join(relative));
+        }
+    }
+
+    Ok(())
+}
+
+fn match_segments(pattern: &[String], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=path.len()).any(|skip| match_segments(rest, &path[skip..]))
+        }
+        Some((first, rest)) => match path.split_first() {
+            Some((name, path)) => match_glob(first, name) && match_segments(rest, path),
+            None => false,
+        },
+    }
+}
+
+fn match_glob(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    // Classic wildcard matching, backtracking to the most recent `*`.
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
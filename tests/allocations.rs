@@ -0,0 +1,60 @@
+//! Lexemes borrow from the source, so scanning allocates nothing per token
+//! beyond growing the token vector.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use jlox::Scanner;
+
+/// Counts, per thread, fresh allocations and reallocations.
+struct Counting;
+
+thread_local! {
+    static ALLOCS: Cell<usize> = const { Cell::new(0) };
+    static REALLOCS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        REALLOCS.with(|n| n.set(n.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Fresh allocations and reallocations made while scanning `source`, and
+/// the number of tokens it holds.
+fn scan_counts(source: &str) -> (usize, usize, usize) {
+    let mut scanner = Scanner::new(source);
+    let before = (ALLOCS.with(Cell::get), REALLOCS.with(Cell::get));
+    scanner.scan_tokens();
+    let after = (ALLOCS.with(Cell::get), REALLOCS.with(Cell::get));
+    assert!(scanner.errors().is_empty());
+    (after.0 - before.0, after.1 - before.1, scanner.tokens().len())
+}
+
+#[test]
+fn punctuation_allocates_a_constant_number_of_times() {
+    let mut fresh = Vec::new();
+    for repeat in [10, 1_000, 100_000] {
+        let source = "(){},.-+;*!=<=>=/ ".repeat(repeat);
+        let (allocs, reallocs, tokens) = scan_counts(&source);
+        assert_eq!(tokens, 14 * repeat);
+        // Only the token vector may grow, and it doubles as it does.
+        assert!(reallocs <= 8, "{} reallocations for {} tokens", reallocs, tokens);
+        fresh.push(allocs);
+    }
+
+    assert!(fresh.iter().all(|&n| n == fresh[0]), "allocations grew with the input: {:?}", fresh);
+}
@@ -0,0 +1,251 @@
+//! Runs the `jlox` binary the way users and editors do.
+
+mod common;
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use common::TempDir;
+
+/// Runs jlox in `dir` with `args` and `stdin`.
+fn jlox(dir: &Path, args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_jlox"))
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("jlox should start");
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().expect("jlox should finish")
+}
+
+fn text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+#[test]
+fn json_diagnostics_point_at_where_errors_start() {
+    let dir = TempDir::new("json-positions");
+    dir.write("broken.lox", "var s = \"abc\nde\nfg");
+    dir.write("caf.lox", "print café @ x;");
+
+    let output = jlox(dir.path(), &["--diagnostics-json", "broken.lox", "caf.lox"], "");
+
+    assert_eq!(output.status.code(), Some(65));
+    let lines: Vec<String> = text(&output.stdout).lines().map(str::to_string).collect();
+    assert_eq!(
+        lines,
+        [
+            "{\"file\":\"broken.lox\",\"line\":1,\"column\":9,\"endColumn\":13,\"severity\":\"error\",\
+             \"code\":\"E003\",\"message\":\"Unterminated string.\",\
+             \"fixSpan\":{\"line\":3,\"column\":3,\"endColumn\":3}}",
+            "{\"file\":\"caf.lox\",\"line\":1,\"column\":10,\"endColumn\":11,\"severity\":\"error\",\
+             \"code\":\"E001\",\"message\":\"Unexpected character.\",\"fixSpan\":null}",
+            "{\"file\":\"caf.lox\",\"line\":1,\"column\":12,\"endColumn\":13,\"severity\":\"error\",\
+             \"code\":\"E001\",\"message\":\"Unexpected character.\",\"fixSpan\":null}",
+        ]
+    );
+}
+
+#[test]
+fn flags_override_the_settings_file() {
+    let dir = TempDir::new("config-override");
+    dir.write("jlox.toml", "[diagnostics]\nmax_errors = 1\n");
+    dir.write("src/three.lox", "@\n@\n@\n");
+
+    let from_file = jlox(dir.path(), &["src/three.lox"], "");
+    let from_flag = jlox(dir.path(), &["--max-errors", "3", "src/three.lox"], "");
+
+    assert_eq!(from_file.status.code(), Some(65));
+    let stderr = text(&from_file.stderr);
+    assert_eq!(stderr.matches("Unexpected character").count(), 1, "{}", stderr);
+    let stderr = text(&from_flag.stderr);
+    assert_eq!(stderr.matches("Unexpected character").count(), 3, "{}", stderr);
+}
+
+#[test]
+fn a_directory_argument_finds_the_settings_inside_it() {
+    let dir = TempDir::new("config-in-dir");
+    dir.write("project/jlox.toml", "[diagnostics]\nmax_errors = 1\n");
+    dir.write("project/three.lox", "@\n@\n@\n");
+
+    let output = jlox(dir.path(), &["--diagnostics-json", "project"], "");
+
+    assert_eq!(output.status.code(), Some(65));
+    let stdout = text(&output.stdout);
+    assert_eq!(stdout.matches("\"code\":\"E001\"").count(), 1, "{}", stdout);
+}
+
+#[test]
+fn full_token_json_names_literals_by_type() {
+    let dir = TempDir::new("tokens-json");
+    dir.write("lit.lox", "\"s\" 2");
+
+    let output = jlox(dir.path(), &["--tokens-json=full", "lit.lox"], "");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        text(&output.stdout),
+        "[\n  \
+         {\"kind\":\"token\",\"type\":\"String\",\"line\":1,\"start\":0,\"end\":3,\"text\":\"\\\"s\\\"\"},\n  \
+         {\"kind\":\"whitespace\",\"type\":null,\"line\":1,\"start\":3,\"end\":4,\"text\":\" \"},\n  \
+         {\"kind\":\"token\",\"type\":\"Number\",\"line\":1,\"start\":4,\"end\":5,\"text\":\"2\"}\n\
+         ]\n"
+    );
+}
+
+#[test]
+fn relaxed_keywords_apply_to_scripts() {
+    let dir = TempDir::new("relaxed-keywords");
+    dir.write("caps.lox", "Print IF");
+
+    let strict = jlox(dir.path(), &["caps.lox"], "");
+    let relaxed = jlox(dir.path(), &["--relaxed-keywords", "caps.lox"], "");
+
+    let strict = text(&strict.stdout);
+    assert!(!strict.contains("token_type: Print"), "{}", strict);
+    let relaxed = text(&relaxed.stdout);
+    assert!(relaxed.contains("token_type: Print"), "{}", relaxed);
+    assert!(relaxed.contains("token_type: If"), "{}", relaxed);
+}
+
+#[test]
+fn version_names_the_package_version_and_features() {
+    let dir = TempDir::new("version");
+
+    let output = jlox(dir.path(), &["--version"], "");
+
+    assert_eq!(output.status.code(), Some(0));
+    let features = if cfg!(feature = "fuzzing") { "fuzzing" } else { "none" };
+    assert_eq!(
+        text(&output.stdout),
+        format!("jlox {}\nfeatures: {}\n", env!("CARGO_PKG_VERSION"), features)
+    );
+}
+
+#[test]
+fn timings_go_to_stderr_only() {
+    let dir = TempDir::new("time");
+    dir.write("a.lox", "var a = 1;\n");
+
+    let plain = jlox(dir.path(), &["a.lox"], "");
+    let table = jlox(dir.path(), &["--time", "a.lox"], "");
+    let json = jlox(dir.path(), &["--time=json", "a.lox"], "");
+
+    for timed in [&table, &json] {
+        assert_eq!(timed.status.code(), Some(0));
+        assert_eq!(timed.stdout, plain.stdout);
+    }
+    assert!(plain.stderr.is_empty(), "{}", text(&plain.stderr));
+    let stderr = text(&table.stderr);
+    assert!(stderr.starts_with("--- timings for a.lox ---\nscan "), "{}", stderr);
+    assert!(stderr.ends_with("tokens             5\n"), "{}", stderr);
+    let stderr = text(&json.stderr);
+    assert!(stderr.starts_with("{\"file\":\"a.lox\",\"scanMs\":"), "{}", stderr);
+    assert!(stderr.ends_with(",\"tokens\":5}\n"), "{}", stderr);
+}
+
+#[test]
+fn errors_name_the_script_they_are_in() {
+    let dir = TempDir::new("two-files");
+    dir.write("a.lox", "var a = 1;\n");
+    dir.write("b.lox", "var b = 2;\nvar c = @;\n");
+
+    let output = jlox(dir.path(), &["a.lox", "b.lox"], "");
+
+    assert_eq!(output.status.code(), Some(65));
+    let stdout = text(&output.stdout);
+    assert!(stdout.contains("lexeme: \"a\""), "{}", stdout);
+    assert!(stdout.contains("lexeme: \"b\""), "{}", stdout);
+    assert_eq!(
+        text(&output.stderr),
+        "[b.lox line 2] Error: Unexpected character.\n"
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_full_disk_is_an_io_error() {
+    let dir = TempDir::new("full-disk");
+    dir.write("a.lox", "var a = 1;\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_jlox"))
+        .arg("a.lox")
+        .current_dir(dir.path())
+        .stdout(File::create("/dev/full").unwrap())
+        .output()
+        .expect("jlox should run");
+
+    assert_eq!(output.status.code(), Some(74));
+    let stderr = text(&output.stderr);
+    assert!(stderr.starts_with("error: "), "{}", stderr);
+    assert!(!stderr.contains("Internal error"), "{}", stderr);
+}
+
+#[test]
+fn json_diagnostics_say_when_errors_were_held_back() {
+    let dir = TempDir::new("json-limit");
+    dir.write("many.lox", &"@\n".repeat(25));
+
+    let output = jlox(dir.path(), &["--diagnostics-json", "many.lox"], "");
+
+    assert_eq!(output.status.code(), Some(65));
+    assert!(output.stderr.is_empty(), "{}", text(&output.stderr));
+    let stdout = text(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 21, "{}", stdout);
+    assert_eq!(
+        lines[20],
+        "{\"errorLimit\":{\"total\":25,\"shown\":20},\
+         \"message\":\"error: aborting due to 25 previous errors (5 not shown)\"}"
+    );
+}
+
+#[test]
+fn unreadable_scripts_are_io_errors() {
+    let dir = TempDir::new("unreadable");
+    dir.write("folder/a.lox", "");
+
+    for script in ["folder", "missing.lox"] {
+        let output = jlox(dir.path(), &[script], "");
+
+        assert_eq!(output.status.code(), Some(74), "{}", script);
+        let stderr = text(&output.stderr);
+        let expected = format!("error: could not read {}: ", script);
+        assert!(stderr.starts_with(&expected), "{}", stderr);
+        assert_eq!(stderr.lines().count(), 1, "{}", stderr);
+    }
+}
+
+#[test]
+fn a_watch_interval_alone_runs_once() {
+    let dir = TempDir::new("watch-interval");
+    dir.write("a.lox", "var a = 1;\n");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_jlox"))
+        .args(["--watch-interval", "10", "a.lox"])
+        .current_dir(dir.path())
+        .stdout(Stdio::null())
+        .spawn()
+        .expect("jlox should start");
+
+    // In watch mode it would keep running until killed.
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if Instant::now() > deadline {
+            child.kill().unwrap();
+            panic!("jlox is still running; --watch-interval started watch mode");
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+    assert_eq!(status.code(), Some(0));
+}
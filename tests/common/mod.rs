@@ -0,0 +1,193 @@
+//! Support for golden-output tests: finding fixture scripts, running them
+//! in-process with captured output, and comparing the results against the
+//! expectations checked in beside them.
+//!
+//! Each `name.lox` fixture is paired with `name.out`, the program output,
+//! and, if it reports any diagnostics, `name.err`. Running with
+//! `UPDATE_GOLDENS=1` rewrites the expectations from the actual results.
+//!
+//! It also has `TempDir` for tests that need real files.
+
+// Each test binary includes this module but uses only some of it.
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::rc::Rc;
+
+use jlox::diagnostic::{error_limit_summary, DiagnosticHandler, InternalError, ScanError};
+use jlox::workspace;
+use jlox::Lox;
+
+/// A buffer that can be handed to `Lox` and still read afterwards.
+#[derive(Clone, Default)]
+pub struct Capture(Rc<RefCell<Vec<u8>>>);
+
+impl Capture {
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).into_owned()
+    }
+}
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Records diagnostics as the stderr handler would print them.
+struct CaptureHandler(Capture);
+
+impl DiagnosticHandler for CaptureHandler {
+    fn on_scan_error(&mut self, e: &ScanError) {
+        writeln!(self.0, "{}", e).unwrap();
+    }
+
+    fn on_internal_error(&mut self, e: &InternalError) {
+        writeln!(self.0, "{}", e).unwrap();
+    }
+
+    fn on_error_limit(&mut self, total: usize, shown: usize) {
+        writeln!(self.0, "{}", error_limit_summary(total, shown)).unwrap();
+    }
+}
+
+/// What running a fixture produced.
+pub struct Outcome {
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Every fixture under `root`, in a stable order.
+pub fn discover(root: &Path) -> Vec<PathBuf> {
+    workspace::collect_files(&[root.to_path_buf()], &[]).expect("fixtures should be readable")
+}
+
+/// Runs the fixture at `path` through the library. Diagnostics name it by
+/// its path relative to `root`, so expectations do not depend on where the
+/// repository is checked out.
+pub fn run_fixture(root: &Path, path: &Path) -> Outcome {
+    let source = fs::read_to_string(path).expect("fixture should be readable");
+    let (stdout, stderr) = (Capture::default(), Capture::default());
+
+    let mut lox = Lox::with_handler(Box::new(CaptureHandler(stderr.clone())));
+    lox.set_output(Box::new(stdout.clone()));
+    lox.set_source_name(Some(display_name(root, path)));
+    lox.run(&source);
+
+    Outcome {
+        stdout: normalize(&stdout.text(), root),
+        stderr: normalize(&stderr.text(), root),
+    }
+}
+
+/// `path` relative to `root`, with `/` separators on every platform.
+pub fn display_name(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Makes output comparable across machines: CRLF becomes LF and any
+/// absolute path to the fixture directory is dropped.
+pub fn normalize(text: &str, root: &Path) -> String {
+    let mut root = root.display().to_string();
+    root.push(std::path::MAIN_SEPARATOR);
+    text.replace("\r\n", "\n").replace(&root, "")
+}
+
+/// Compares `actual` against the expectation at `expected_path`, where a
+/// missing file expects empty output. With `UPDATE_GOLDENS=1` the file is
+/// rewritten instead, or removed when `actual` is empty.
+pub fn check(expected_path: &Path, actual: &str) -> Result<(), String> {
+    if env::var_os("UPDATE_GOLDENS").is_some_and(|v| v == "1") {
+        let result = if actual.is_empty() {
+            fs::remove_file(expected_path).or_else(|e| match e.kind() {
+                io::ErrorKind::NotFound => Ok(()),
+                _ => Err(e),
+            })
+        } else {
+            fs::write(expected_path, actual)
+        };
+        return result.map_err(|e| format!("{}: {}", expected_path.display(), e));
+    }
+
+    let expected = match fs::read_to_string(expected_path) {
+        Ok(text) => text.replace("\r\n", "\n"),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("{}: {}", expected_path.display(), e)),
+    };
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(format!("{}\n{}", expected_path.display(), diff(&expected, actual)))
+    }
+}
+
+/// A line-by-line diff: `-` for expected lines that are missing or
+/// different, `+` for what was produced in their place.
+pub fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut out = String::new();
+
+    for i in 0..expected.len().max(actual.len()) {
+        let (want, got) = (expected.get(i), actual.get(i));
+        if want == got {
+            continue;
+        }
+        out.push_str(&format!("@@ line {} @@\n", i + 1));
+        if let Some(line) = want {
+            out.push_str(&format!("-{}\n", line));
+        }
+        if let Some(line) = got {
+            out.push_str(&format!("+{}\n", line));
+        }
+    }
+
+    out
+}
+
+/// A scratch directory under the system temp dir, removed when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// `name` must be unique among the tests of one test binary.
+    pub fn new(name: &str) -> Self {
+        let path = env::temp_dir().join(format!("jlox-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).expect("temp dir should be creatable");
+        TempDir(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Writes `contents` to `relative`, creating any missing directories.
+    pub fn write(&self, relative: &str, contents: &str) -> PathBuf {
+        let path = self.0.join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("temp dir should be writable");
+        }
+        fs::write(&path, contents).expect("temp dir should be writable");
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
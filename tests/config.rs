@@ -0,0 +1,89 @@
+//! Reading `jlox.toml` settings and finding the file to read.
+
+mod common;
+
+use jlox::config::{self, Config, ConfigError};
+
+use common::TempDir;
+
+#[test]
+fn every_setting_is_read() {
+    let text = "[scanner]\ncase_insensitive_keywords = true\n\n\
+                [diagnostics]\nmax_errors = 3\nmax_lexeme_preview = 8\n\n\
+                [repl]\nprompt = \"lox> \"\nbanner = false\n";
+
+    let (config, warnings) = Config::parse(text).unwrap();
+
+    assert!(warnings.is_empty(), "{:?}", warnings);
+    assert!(config.scanner.case_insensitive_keywords);
+    assert_eq!(config.scanner.diagnostics.max_errors, 3);
+    assert_eq!(config.scanner.diagnostics.max_lexeme_preview, 8);
+    assert_eq!(config.repl.prompt, "lox> ");
+    assert!(!config.repl.banner);
+}
+
+#[test]
+fn a_value_of_the_wrong_type_names_the_key() {
+    let error = Config::parse("[repl]\n\nbanner = \"no\"\n").unwrap_err();
+
+    assert_eq!(
+        error,
+        ConfigError {
+            path: None,
+            line: 3,
+            key: "repl.banner".to_string(),
+            message: "must be a boolean, found a string".to_string(),
+        }
+    );
+    assert_eq!(error.to_string(), "3: 'repl.banner' must be a boolean, found a string");
+}
+
+#[test]
+fn unknown_settings_are_warnings() {
+    let text = "[scanner]\ncolour = true\n[plugins]\nname = \"x\"\n";
+
+    let (config, warnings) = Config::parse(text).unwrap();
+
+    assert_eq!(config, Config::default());
+    assert_eq!(
+        warnings,
+        [
+            "line 2: unknown setting 'scanner.colour'",
+            "line 4: unknown setting 'plugins.name'",
+        ]
+    );
+}
+
+#[test]
+fn comments_end_lines_but_not_strings() {
+    let text = "# settings\n[repl] # the prompt\nprompt = \"#1 \\\"q#\\\" \" # not part of it\n";
+
+    let (config, warnings) = Config::parse(text).unwrap();
+
+    assert!(warnings.is_empty(), "{:?}", warnings);
+    assert_eq!(config.repl.prompt, "#1 \"q#\" ");
+}
+
+#[test]
+fn the_nearest_file_is_found_from_a_nested_directory() {
+    let dir = TempDir::new("config-discover");
+    let outer = dir.write("jlox.toml", "");
+    let inner = dir.write("project/jlox.toml", "");
+    dir.write("project/src/deep/main.lox", "");
+
+    let nested = dir.path().join("project/src/deep");
+    assert_eq!(config::discover(&nested), Some(inner));
+    assert_eq!(config::discover(&dir.path().join("other")), Some(outer.clone()));
+    assert_eq!(config::discover(dir.path()), Some(outer));
+}
+
+#[test]
+fn errors_loading_a_file_name_it() {
+    let dir = TempDir::new("config-load");
+    let path = dir.write("jlox.toml", "[diagnostics]\nmax_errors = -1\n");
+
+    let error = Config::load(&path).unwrap_err();
+
+    assert_eq!(error.path, Some(path));
+    assert_eq!(error.key, "diagnostics.max_errors");
+}
@@ -0,0 +1,117 @@
+//! Diagnostics reach the installed handler exactly as reported.
+
+mod common;
+
+use std::cell::RefCell;
+use std::io::{self, Read};
+use std::rc::Rc;
+
+use jlox::diagnostic::{DiagnosticHandler, FixSpan, ScanError, ScanErrorKind};
+use jlox::Lox;
+
+use common::Capture;
+
+#[derive(Clone, Default)]
+struct Collect(Rc<RefCell<Vec<ScanError>>>);
+
+impl DiagnosticHandler for Collect {
+    fn on_scan_error(&mut self, e: &ScanError) {
+        self.0.borrow_mut().push(e.clone());
+    }
+}
+
+fn error(line: usize, column: usize, end_column: usize, kind: ScanErrorKind, message: &str) -> ScanError {
+    ScanError {
+        file: Some("kinds.lox".to_string()),
+        line,
+        column,
+        end_column,
+        kind,
+        message: message.to_string(),
+        fix_span: None,
+    }
+}
+
+// `ScanErrorKind::InvalidNumber` is not covered: digits with an optional
+// fraction, which is all the scanner passes on, always parse.
+#[test]
+fn a_collecting_handler_sees_every_scan_error_kind() {
+    let source = format!(
+        "var a = @;\nvar b = 12ab;\nvar c = 1{};\nvar d = {};\nvar e = \"open",
+        "0".repeat(40),
+        "9".repeat(600)
+    );
+    let collected = Collect::default();
+    let mut lox = Lox::with_handler(Box::new(collected.clone()));
+    lox.set_output(Box::new(io::sink()));
+    lox.set_source_name(Some("kinds.lox".to_string()));
+
+    lox.check(&source);
+
+    assert!(lox.had_error);
+    let mut unterminated = error(5, 9, 14, ScanErrorKind::UnterminatedString, "Unterminated string.");
+    unterminated.fix_span = Some(FixSpan {
+        line: 5,
+        column: 14,
+        end_column: 14,
+    });
+    assert_eq!(
+        *collected.0.borrow(),
+        [
+            error(1, 9, 10, ScanErrorKind::UnexpectedCharacter, "Unexpected character."),
+            error(2, 9, 13, ScanErrorKind::InvalidNumericLiteral, "Invalid numeric literal '12ab'."),
+            error(3, 9, 50, ScanErrorKind::NumberOutOfRange, "Number literal out of range."),
+            error(4, 9, 609, ScanErrorKind::NumberTooLong, "Number literal is longer than 512 characters."),
+            unterminated,
+        ]
+    );
+}
+
+struct Failing;
+
+impl Read for Failing {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::other("disk on fire"))
+    }
+}
+
+#[test]
+fn read_failures_are_not_scan_errors() {
+    let collected = Collect::default();
+    let mut lox = Lox::with_handler(Box::new(collected.clone()));
+
+    lox.run_reader(Failing);
+
+    assert!(collected.0.borrow().is_empty());
+    assert!(!lox.had_error);
+    let error = lox.read_error.take().expect("the read error should be kept");
+    assert_eq!(error.to_string(), "disk on fire");
+}
+
+#[test]
+fn a_long_line_reports_true_columns_and_echoes_little() {
+    // One 100k-char line: a long string, then a long bad literal starting
+    // at column 50,000.
+    let prefix = format!("var s = \"{}\"; ", "x".repeat(49_999 - 12));
+    let literal = format!("9{}", "q".repeat(30_000));
+    let source = format!("{}{};{}", prefix, literal, " ".repeat(19_999));
+    assert_eq!(source.chars().count(), 100_000);
+
+    let collected = Collect::default();
+    let output = Capture::default();
+    let mut lox = Lox::with_handler(Box::new(collected.clone()));
+    lox.set_output(Box::new(output.clone()));
+    lox.run(&source);
+
+    let errors = collected.0.borrow();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind, ScanErrorKind::InvalidNumericLiteral);
+    assert_eq!((errors[0].line, errors[0].column, errors[0].end_column), (1, 50_000, 80_001));
+    assert!(errors[0].message.len() < 100, "{}", errors[0].message);
+
+    let output = output.text();
+    assert!(output.contains("(… 49949 more characters)"), "{}", output);
+    for line in output.lines() {
+        assert!(line.len() < 300, "{}", line);
+    }
+}
@@ -0,0 +1,24 @@
+//! Every input in the fuzz corpus, replayed through the fuzz target so a
+//! crash found once stays fixed. Run with `--features fuzzing`.
+
+#![cfg(feature = "fuzzing")]
+
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn the_scan_corpus_passes_the_fuzz_target() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus/scan");
+    let mut entries: Vec<_> = fs::read_dir(&root)
+        .expect("the corpus should be readable")
+        .map(|entry| entry.expect("the corpus should be readable").path())
+        .collect();
+    entries.sort();
+    assert!(!entries.is_empty(), "no inputs in {}", root.display());
+
+    for path in entries {
+        let data = fs::read(&path).expect("corpus inputs should be readable");
+        let result = std::panic::catch_unwind(|| jlox::fuzz::scan(&data));
+        assert!(result.is_ok(), "{} fails the fuzz target", path.display());
+    }
+}
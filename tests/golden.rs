@@ -0,0 +1,31 @@
+//! Runs every fixture under `tests/scripts` and compares its output with the
+//! `.out` and `.err` files beside it. See `common` for the conventions.
+
+mod common;
+
+use std::path::Path;
+
+#[test]
+fn fixtures_match_golden_output() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("scripts");
+    let fixtures = common::discover(&root);
+    assert!(!fixtures.is_empty(), "no fixtures found under {}", root.display());
+
+    let mut failures = Vec::new();
+    for fixture in &fixtures {
+        let outcome = common::run_fixture(&root, fixture);
+        for (extension, actual) in [("out", &outcome.stdout), ("err", &outcome.stderr)] {
+            if let Err(diff) = common::check(&fixture.with_extension(extension), actual) {
+                failures.push(diff);
+            }
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} golden files differ across {} fixtures:\n\n{}\nRun with UPDATE_GOLDENS=1 to accept the new output.",
+        failures.len(),
+        fixtures.len(),
+        failures.join("\n")
+    );
+}
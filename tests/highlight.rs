@@ -0,0 +1,48 @@
+//! Classifying source text for syntax highlighting.
+
+use jlox::highlight::{highlight, highlight_html, TokenClass};
+
+#[test]
+fn a_mixed_snippet_is_classified_span_by_span() {
+    let source = "if (x >= 1.5) print \"hi\"; // done\n@";
+
+    let spans: Vec<(&str, TokenClass)> = highlight(source)
+        .iter()
+        .map(|span| (&source[span.start..span.end], span.class))
+        .collect();
+
+    use TokenClass::*;
+    assert_eq!(
+        spans,
+        [
+            ("if", Keyword),
+            (" ", Whitespace),
+            ("(", Punctuation),
+            ("x", Identifier),
+            (" ", Whitespace),
+            (">=", Operator),
+            (" ", Whitespace),
+            ("1.5", Number),
+            (")", Punctuation),
+            (" ", Whitespace),
+            ("print", Keyword),
+            (" ", Whitespace),
+            ("\"hi\"", String),
+            (";", Punctuation),
+            (" ", Whitespace),
+            ("// done", Comment),
+            ("\n", Whitespace),
+            ("@", Error),
+        ]
+    );
+}
+
+#[test]
+fn html_wraps_everything_but_whitespace() {
+    assert_eq!(
+        highlight_html("a < \"&\""),
+        "<pre class=\"lox\"><span class=\"identifier\">a</span> \
+         <span class=\"operator\">&lt;</span> \
+         <span class=\"string\">&quot;&amp;&quot;</span></pre>\n"
+    );
+}
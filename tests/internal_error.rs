@@ -0,0 +1,115 @@
+//! Panics inside the pipeline become internal-error diagnostics, but output
+//! that nobody reads any more or that cannot be written is not one.
+
+mod common;
+
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use jlox::diagnostic::{DiagnosticHandler, InternalError, ScanError};
+use jlox::repl::{self, ReplConfig};
+use jlox::Lox;
+
+use common::Capture;
+
+/// Panics on the first scan error, standing in for a bug in jlox, and
+/// records the internal errors that result.
+#[derive(Clone, Default)]
+struct PanickingHandler {
+    internal_errors: Rc<RefCell<Vec<String>>>,
+}
+
+impl DiagnosticHandler for PanickingHandler {
+    fn on_scan_error(&mut self, e: &ScanError) {
+        panic!("deliberate failure at {}", e.message);
+    }
+
+    fn on_internal_error(&mut self, e: &InternalError) {
+        self.internal_errors.borrow_mut().push(e.to_string());
+    }
+}
+
+#[test]
+fn a_panic_is_reported_and_the_session_continues() {
+    let handler = PanickingHandler::default();
+    let output = Capture::default();
+    let mut lox = Lox::with_handler(Box::new(handler.clone()));
+    lox.set_output(Box::new(output.clone()));
+
+    let config = ReplConfig {
+        prompt: String::new(),
+        banner: false,
+    };
+    let input: &[u8] = b"print\n@\nvar\n";
+    repl::run(&mut lox, &config, input, io::sink()).unwrap();
+
+    assert_eq!(
+        *handler.internal_errors.borrow(),
+        ["[line 1] Internal error: deliberate failure at Unexpected character.\n\
+          This is a bug in jlox; please report it along with the script that triggered it."]
+    );
+    let output = output.text();
+    assert!(output.contains("token_type: Print"), "{}", output);
+    assert!(output.contains("token_type: Var"), "{}", output);
+}
+
+struct ClosedPipe;
+
+impl Write for ClosedPipe {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn a_closed_output_is_not_an_internal_error() {
+    let handler = PanickingHandler::default();
+    let mut lox = Lox::with_handler(Box::new(handler.clone()));
+    lox.set_output(Box::new(ClosedPipe));
+
+    lox.run("var a = 1;\nprint a;\n");
+
+    assert!(!lox.had_internal_error);
+    assert!(handler.internal_errors.borrow().is_empty());
+}
+
+struct FullDisk;
+
+impl Write for FullDisk {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::ErrorKind::StorageFull.into())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn a_failed_write_is_an_io_error_not_an_internal_one() {
+    let handler = PanickingHandler::default();
+    let mut lox = Lox::with_handler(Box::new(handler.clone()));
+    lox.set_output(Box::new(FullDisk));
+
+    lox.run("var a = 1;\nprint a;\n");
+
+    assert!(!lox.had_internal_error);
+    assert!(handler.internal_errors.borrow().is_empty());
+    let error = lox.output_error.take().expect("the write error should be kept");
+    assert_eq!(error.kind(), io::ErrorKind::StorageFull);
+
+    let mut lox = Lox::with_handler(Box::new(handler.clone()));
+    lox.set_output(Box::new(FullDisk));
+    let config = ReplConfig {
+        prompt: String::new(),
+        banner: false,
+    };
+    let input: &[u8] = b"print;\n";
+    let error = repl::run(&mut lox, &config, input, io::sink()).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::StorageFull);
+}
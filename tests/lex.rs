@@ -0,0 +1,58 @@
+//! Lossless tokenization reproduces its input byte for byte.
+
+use jlox::diagnostic::ScanErrorKind;
+use jlox::lex::{lex_full, FullTokenKind};
+use jlox::TokenType;
+
+fn reconstruct(source: &str) -> String {
+    lex_full(source).iter().map(|entry| entry.text).collect()
+}
+
+#[test]
+fn comments_and_crlf_line_breaks_are_kept() {
+    let source = "// header\r\nvar a = 1; // trailing\r\n\r\n  print a;\r\n// last";
+
+    assert_eq!(reconstruct(source), source);
+
+    let kinds: Vec<_> = lex_full(source).iter().map(|entry| entry.kind).collect();
+    assert_eq!(
+        kinds[..3],
+        [
+            FullTokenKind::Comment,
+            FullTokenKind::Whitespace,
+            FullTokenKind::Token(TokenType::Var),
+        ]
+    );
+    assert_eq!(kinds.last(), Some(&FullTokenKind::Comment));
+}
+
+#[test]
+fn an_unterminated_string_at_the_end_is_one_error() {
+    let source = "print \"open\r\nstill open";
+
+    let entries = lex_full(source);
+
+    assert_eq!(reconstruct(source), source);
+    let last = entries.last().unwrap();
+    assert_eq!(last.kind, FullTokenKind::Error(ScanErrorKind::UnterminatedString));
+    assert_eq!((last.text, last.line), ("\"open\r\nstill open", 1));
+}
+
+#[test]
+fn spans_and_lines_locate_each_entry() {
+    let source = "a\n  \"b\nc\" d";
+
+    for entry in lex_full(source) {
+        assert_eq!(&source[entry.span.range()], entry.text);
+    }
+    let lines: Vec<_> = lex_full(source).iter().map(|entry| (entry.text, entry.line)).collect();
+    assert_eq!(lines, [("a", 1), ("\n  ", 1), ("\"b\nc\"", 2), (" ", 3), ("d", 3)]);
+}
+
+#[test]
+fn token_names_leave_out_literal_values() {
+    assert_eq!(TokenType::String("text").name(), "String");
+    assert_eq!(TokenType::Number(1.5).name(), "Number");
+    assert_eq!(TokenType::QuestionDot.name(), "QuestionDot");
+    assert_eq!(TokenType::Eof.name(), "Eof");
+}
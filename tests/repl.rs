@@ -0,0 +1,138 @@
+//! The REPL driven with in-memory input, checking what it writes around the
+//! program's own output.
+
+mod common;
+
+use std::io::{self, BufReader, Read};
+
+use jlox::repl::{self, ReplConfig};
+use jlox::Lox;
+
+use common::Capture;
+
+/// Runs a session over `input` and returns what went to the prompt output.
+fn session(config: &ReplConfig, input: &str) -> String {
+    let mut lox = Lox::new();
+    lox.set_output(Box::new(io::sink()));
+    let mut prompt_out = Vec::new();
+    repl::run(&mut lox, config, input.as_bytes(), &mut prompt_out).unwrap();
+    String::from_utf8(prompt_out).unwrap()
+}
+
+fn config(prompt: &str, banner: bool) -> ReplConfig {
+    ReplConfig {
+        prompt: prompt.to_string(),
+        banner,
+    }
+}
+
+#[test]
+fn the_prompt_is_written_before_every_line() {
+    assert_eq!(session(&config("> ", false), "print;\nvar;\n"), "> > > \n");
+}
+
+#[test]
+fn an_empty_prompt_writes_nothing() {
+    assert_eq!(session(&config("", false), "print;\nvar;\n"), "");
+}
+
+#[test]
+fn the_banner_names_the_version() {
+    let banner = format!("jlox {}\n", env!("CARGO_PKG_VERSION"));
+    assert_eq!(session(&config("", true), "print;\n"), banner);
+    assert_eq!(session(&config("> ", true), ""), format!("{}> \n", banner));
+    assert_eq!(session(&config("", false), ""), "");
+}
+
+#[test]
+fn the_default_has_a_prompt_and_a_banner() {
+    let output = session(&ReplConfig::default(), "print;\n");
+    assert_eq!(output, format!("jlox {}\n>> >> \n", env!("CARGO_PKG_VERSION")));
+}
+
+#[test]
+fn unfinished_entries_continue_after_a_different_prompt() {
+    assert_eq!(
+        session(&config("> ", false), "fun f() {\nprint;\n}\nvar;\n"),
+        "> ... ... > > \n"
+    );
+}
+
+#[test]
+fn end_of_input_runs_the_pending_entry_and_ends_the_line() {
+    let output = Capture::default();
+    let mut lox = Lox::new();
+    lox.set_output(Box::new(output.clone()));
+    let mut prompt_out = Vec::new();
+
+    let input: &[u8] = b"fun f() {\nprint";
+    repl::run(&mut lox, &config("> ", false), input, &mut prompt_out).unwrap();
+
+    assert_eq!(prompt_out, b"> ... ... > \n");
+    let output = output.text();
+    assert!(output.contains("token_type: Print"), "{}", output);
+}
+
+/// Hands out `data`, then fails once with `kind` and finishes if asked again.
+struct Failing {
+    data: &'static [u8],
+    kind: Option<io::ErrorKind>,
+}
+
+impl Read for Failing {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.data.is_empty() {
+            let n = buf.len().min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            return Ok(n);
+        }
+        match self.kind.take() {
+            Some(kind) => Err(io::Error::new(kind, "read failed")),
+            None => Ok(0),
+        }
+    }
+}
+
+#[test]
+fn a_read_error_ends_the_session_with_it() {
+    let input = BufReader::new(Failing {
+        data: b"print;\n",
+        kind: Some(io::ErrorKind::InvalidData),
+    });
+    let mut lox = Lox::new();
+    lox.set_output(Box::new(io::sink()));
+
+    let error = repl::run(&mut lox, &config("", false), input, io::sink()).unwrap_err();
+
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn an_interrupted_read_is_retried() {
+    let input = BufReader::new(Failing {
+        data: b"print;\n",
+        kind: Some(io::ErrorKind::Interrupted),
+    });
+    let mut lox = Lox::new();
+    lox.set_output(Box::new(io::sink()));
+    let mut prompt_out = Vec::new();
+
+    repl::run(&mut lox, &config("> ", false), input, &mut prompt_out).unwrap();
+
+    assert_eq!(prompt_out, b"> > \n");
+}
+
+#[test]
+fn open_strings_and_brackets_continue_the_entry() {
+    let prompt = config("> ", false);
+
+    assert_eq!(session(&prompt, "print \"a\nb\";\n"), "> ... > \n");
+    assert_eq!(
+        session(&prompt, "print \"a poem\nreturn to me\nlater\";\n"),
+        "> ... ... > \n"
+    );
+    assert_eq!(session(&prompt, "f(1,\n2);\n"), "> ... > \n");
+    assert_eq!(session(&prompt, "{\n{ x; }\n}\n"), "> ... ... > \n");
+    assert_eq!(session(&prompt, ")(\n);\n"), "> > > \n");
+}
@@ -0,0 +1,38 @@
+//! Round-trips every offset of some awkward files through `SourceFile`.
+
+use jlox::source::SourceFile;
+
+const FILES: [&str; 7] = [
+    "",
+    "\n",
+    "print 1;",
+    "var a = 1;\nprint a;\n",
+    "var a = 1;\r\nprint a;\r\n",
+    "\r\n\r\nlast line without a break",
+    "var café = \"naïve 🦀\";\n\n€;\r\n",
+];
+
+#[test]
+fn offsets_round_trip_through_positions() {
+    for text in FILES {
+        let file = SourceFile::new("test.lox", text);
+        for (offset, _) in text.char_indices().chain([(text.len(), ' ')]) {
+            let (line, column) = file.line_col(offset);
+            assert_eq!(file.offset(line, column), Some(offset), "{:?} at {}", text, offset);
+        }
+    }
+}
+
+#[test]
+fn lines_exclude_their_breaks() {
+    let file = SourceFile::new("test.lox", "a\r\nb€\n\nc");
+
+    assert_eq!(file.line_count(), 4);
+    let lines: Vec<&str> = (1..=4).map(|line| file.line_text(line)).collect();
+    assert_eq!(lines, ["a", "b€", "", "c"]);
+    assert_eq!(file.line_span(2).range(), 3..7);
+    assert_eq!(file.line_col(7), (2, 3));
+    assert_eq!(file.offset(2, 4), None);
+    assert_eq!(file.offset(5, 1), None);
+    assert_eq!(file.offset(1, 0), None);
+}
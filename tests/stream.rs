@@ -0,0 +1,71 @@
+//! The streaming scanner must produce exactly what the in-memory scanner
+//! does, however the input is split into reads.
+
+use std::io::{self, Read};
+
+use jlox::stream::StreamingScanner;
+use jlox::Scanner;
+
+/// Hands out at most `chunk` bytes per read.
+struct Trickle<'a> {
+    data: &'a [u8],
+    chunk: usize,
+}
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.chunk.min(buf.len()).min(self.data.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
+    }
+}
+
+/// A few thousand lines touching every kind of token, multi-byte text,
+/// multi-line strings and errors, including strings cut short by recovery,
+/// with an unterminated string at the end.
+fn large_input() -> String {
+    let mut source = String::new();
+    for i in 0..2_000 {
+        source.push_str(&format!(
+            "var v{} = (a{} + 3.25) * -b / c; // note {}\n",
+            i, i, i
+        ));
+        match i % 5 {
+            0 => source.push_str("if (x >= 1 and y != nil) { print \"café ☕\"; }\n"),
+            1 => source.push_str("fun f(p, q) { return p <= q or !true; }\n"),
+            2 => source.push_str("var s = \"two\nlines\"; x == y; z < w;\n"),
+            3 => source.push_str("var bad = 12ab @ #; \"open\n  print \"recovered\";\n"),
+            _ => source.push_str("while (false) this.super.class = 0.5;\r\n"),
+        }
+    }
+    source.push_str("var last = \"never closed\nprint done;\n");
+    source
+}
+
+#[test]
+fn tiny_reads_match_the_in_memory_scanner() {
+    let source = large_input();
+    let mut scanner = Scanner::new(&source);
+    scanner.scan_tokens();
+    let tokens: Vec<String> = scanner.tokens().iter().map(|t| format!("{:?}", t)).collect();
+    let errors: Vec<String> = scanner.errors().iter().map(|e| format!("{:?}", e)).collect();
+    assert!(!errors.is_empty());
+
+    for chunk in [1, 2, 3] {
+        let reader = Trickle {
+            data: source.as_bytes(),
+            chunk,
+        };
+        let (mut streamed_tokens, mut streamed_errors) = (Vec::new(), Vec::new());
+        for item in StreamingScanner::new(reader) {
+            match item {
+                Ok(token) => streamed_tokens.push(format!("{:?}", token.as_token())),
+                Err(error) => streamed_errors.push(format!("{:?}", error)),
+            }
+        }
+
+        assert_eq!(streamed_tokens, tokens, "tokens with {}-byte reads", chunk);
+        assert_eq!(streamed_errors, errors, "errors with {}-byte reads", chunk);
+    }
+}
@@ -0,0 +1,98 @@
+//! The watcher driven by a fake filesystem and a synthetic clock.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
+
+use jlox::watch::{timestamp, Probe, Watcher};
+
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Modification times that the test can change while the watcher holds it.
+#[derive(Clone, Default)]
+struct FakeProbe(Rc<RefCell<HashMap<PathBuf, SystemTime>>>);
+
+impl FakeProbe {
+    fn touch(&self, path: &str, secs: u64) {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        self.0.borrow_mut().insert(PathBuf::from(path), time);
+    }
+}
+
+impl Probe for FakeProbe {
+    fn modified(&mut self, path: &Path) -> Option<SystemTime> {
+        self.0.borrow().get(path).copied()
+    }
+}
+
+fn watcher(probe: &FakeProbe) -> Watcher<FakeProbe> {
+    probe.touch("a.lox", 1);
+    probe.touch("b.lox", 1);
+    let paths = vec![PathBuf::from("a.lox"), PathBuf::from("b.lox")];
+    Watcher::new(paths, probe.clone(), DEBOUNCE)
+}
+
+fn ms(n: u64) -> Duration {
+    Duration::from_millis(n)
+}
+
+#[test]
+fn unchanged_files_are_not_re_run() {
+    let probe = FakeProbe::default();
+    let mut watcher = watcher(&probe);
+    let start = Instant::now();
+
+    for step in 0..10 {
+        assert!(!watcher.poll(start + ms(step * 50)));
+    }
+}
+
+#[test]
+fn a_change_is_re_run_once_the_debounce_has_elapsed() {
+    let probe = FakeProbe::default();
+    let mut watcher = watcher(&probe);
+    let start = Instant::now();
+
+    probe.touch("b.lox", 2);
+    assert!(!watcher.poll(start));
+    assert!(!watcher.poll(start + ms(99)));
+    assert!(watcher.poll(start + ms(100)));
+    assert!(!watcher.poll(start + ms(200)));
+}
+
+#[test]
+fn a_burst_of_saves_is_re_run_once() {
+    let probe = FakeProbe::default();
+    let mut watcher = watcher(&probe);
+    let start = Instant::now();
+
+    let mut runs = 0;
+    for step in 0..5 {
+        probe.touch("a.lox", 2 + step);
+        runs += watcher.poll(start + ms(step * 50)) as usize;
+    }
+    for step in 5..20 {
+        runs += watcher.poll(start + ms(step * 50)) as usize;
+    }
+
+    assert_eq!(runs, 1);
+}
+
+#[test]
+fn a_deleted_file_counts_as_a_change() {
+    let probe = FakeProbe::default();
+    let mut watcher = watcher(&probe);
+    let start = Instant::now();
+
+    probe.0.borrow_mut().remove(Path::new("a.lox"));
+    assert!(!watcher.poll(start));
+    assert!(watcher.poll(start + DEBOUNCE));
+}
+
+#[test]
+fn timestamps_are_utc_times_of_day() {
+    let time = SystemTime::UNIX_EPOCH + Duration::from_secs(3 * 86_400 + 13 * 3600 + 5 * 60 + 9);
+    assert_eq!(timestamp(time), "13:05:09");
+}
@@ -0,0 +1,94 @@
+//! Expanding directories into the scripts beneath them.
+
+mod common;
+
+use std::path::PathBuf;
+
+use jlox::workspace::{self, IgnoreRules};
+
+use common::{display_name, TempDir};
+
+/// A project with scripts at several depths and a little of everything to
+/// ignore.
+fn project(name: &str) -> TempDir {
+    let dir = TempDir::new(name);
+    dir.write("main.lox", "");
+    dir.write("notes.txt", "");
+    dir.write("lib/util.lox", "");
+    dir.write("lib/util_test.lox", "");
+    dir.write("lib/gen/parser.lox", "");
+    dir.write("build/out.lox", "");
+    dir.write("vendor/a/b/dep.lox", "");
+    dir.write("docs/build", "");
+    dir
+}
+
+fn collect(dir: &TempDir, excludes: &[&str]) -> Vec<String> {
+    let excludes: Vec<String> = excludes.iter().map(|e| e.to_string()).collect();
+    workspace::collect_files(&[dir.path().to_path_buf()], &excludes)
+        .unwrap()
+        .iter()
+        .map(|path| display_name(dir.path(), path))
+        .collect()
+}
+
+#[test]
+fn directories_contribute_their_lox_files_in_order() {
+    let dir = project("workspace-all");
+
+    assert_eq!(
+        collect(&dir, &[]),
+        [
+            "build/out.lox",
+            "lib/gen/parser.lox",
+            "lib/util.lox",
+            "lib/util_test.lox",
+            "main.lox",
+            "vendor/a/b/dep.lox",
+        ]
+    );
+}
+
+#[test]
+fn the_ignore_file_and_excludes_both_apply() {
+    let dir = project("workspace-ignored");
+    dir.write(
+        ".loxignore",
+        "# generated output\nbuild/\n\nvendor/**/dep.lox\n",
+    );
+
+    assert_eq!(
+        collect(&dir, &["*_test.lox", "lib/gen"]),
+        ["lib/util.lox", "main.lox"]
+    );
+}
+
+#[test]
+fn files_named_directly_are_never_ignored() {
+    let dir = project("workspace-direct");
+    dir.write(".loxignore", "*.lox\n");
+    let script = dir.path().join("lib/util.lox");
+
+    let files = workspace::collect_files(&[script.clone(), dir.path().to_path_buf()], &[]).unwrap();
+
+    assert_eq!(files, [script]);
+}
+
+#[test]
+fn a_trailing_slash_matches_directories_only() {
+    let rules = IgnoreRules::parse("build/\n");
+
+    assert!(rules.is_ignored(&PathBuf::from("build"), true));
+    assert!(rules.is_ignored(&PathBuf::from("docs/build"), true));
+    assert!(!rules.is_ignored(&PathBuf::from("docs/build"), false));
+}
+
+#[test]
+fn double_stars_match_any_number_of_directories() {
+    let rules = IgnoreRules::parse("a/**/x.lox\n");
+
+    assert!(rules.is_ignored(&PathBuf::from("a/x.lox"), false));
+    assert!(rules.is_ignored(&PathBuf::from("a/b/c/x.lox"), false));
+    assert!(!rules.is_ignored(&PathBuf::from("b/a/x.lox"), false));
+    assert!(!rules.is_ignored(&PathBuf::from("x.lox"), false));
+}